@@ -0,0 +1,7600 @@
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    collections::{HashMap, HashSet, VecDeque},
+    env,
+    ffi::{c_void, OsStr, OsString},
+    fmt, fs,
+    hash::{BuildHasher, DefaultHasher, Hash, Hasher},
+    io::{self, BufRead, Write},
+    os::windows::{ffi::OsStrExt, io::AsRawHandle, process::CommandExt},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdout, Command, Stdio},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc, Condvar, Mutex, OnceLock, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use native_windows_gui as nwg;
+use regex::Regex;
+#[cfg(feature = "audio")]
+use windows::Win32::{
+    Media::Audio::{
+        eMultimedia, eRender, AudioSessionStateActive, IAudioSessionControl2,
+        IAudioSessionManager2, IMMDeviceEnumerator, MMDeviceEnumerator,
+    },
+    System::Com::{CLSCTX_ALL, COINIT_MULTITHREADED},
+};
+use windows::{
+    core::{w, Interface, BSTR, GUID, PCWSTR, PWSTR},
+    Wdk::System::SystemServices::RtlGetVersion,
+    Win32::{
+        Foundation::{
+            BOOL, CloseHandle, GetLastError, ERROR_ALREADY_EXISTS, ERROR_FILE_NOT_FOUND,
+            E_ACCESSDENIED, FILETIME, HANDLE, HINSTANCE, HWND, LPARAM, LRESULT, POINT, RECT,
+            WAIT_OBJECT_0, WPARAM,
+        },
+        Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY},
+        System::{
+            Com::{
+                CoCreateInstance, CoInitializeEx, CoUninitialize, IPersistFile,
+                CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED, STGM_READ,
+            },
+            Console::GetConsoleWindow,
+            Diagnostics::Debug::{
+                MiniDumpWithThreadInfo, MiniDumpWriteDump, SetUnhandledExceptionFilter,
+                EXCEPTION_POINTERS, MINIDUMP_EXCEPTION_INFORMATION,
+            },
+            Diagnostics::ToolHelp::{
+                CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+                TH32CS_SNAPPROCESS,
+            },
+            LibraryLoader::GetModuleHandleW,
+            Power::{
+                SetThreadExecutionState, ES_CONTINUOUS, ES_DISPLAY_REQUIRED, ES_SYSTEM_REQUIRED,
+            },
+            Registry::{RegDeleteKeyValueW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ},
+            RemoteDesktop::{
+                ProcessIdToSessionId, WTSQueryUserToken, WTSRegisterSessionNotification,
+                WTSUnRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION,
+            },
+            StationsAndDesktops::{
+                CloseDesktop, GetProcessWindowStation, GetUserObjectInformationW, OpenInputDesktop,
+                SwitchDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_SWITCHDESKTOP, UOI_FLAGS,
+                USEROBJECTFLAGS,
+            },
+            SystemInformation::{GetLocalTime, GetSystemTime, GetTickCount, OSVERSIONINFOW},
+            Threading::{
+                AttachThreadInput, CreateEventW, CreateMutexW, CreateProcessAsUserW,
+                GetCurrentProcess, GetCurrentProcessId, GetCurrentThread, GetCurrentThreadId,
+                GetExitCodeProcess, GetProcessTimes, OpenEventW, OpenMutexW, OpenProcess,
+                OpenProcessToken, QueryFullProcessImageNameW, SetEvent, SetPriorityClass,
+                SetThreadPriority, TerminateProcess, WaitForSingleObject, HIGH_PRIORITY_CLASS,
+                INFINITE, NORMAL_PRIORITY_CLASS, PROCESS_CREATION_FLAGS, PROCESS_INFORMATION,
+                PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION, REALTIME_PRIORITY_CLASS,
+                STARTUPINFOW, SYNCHRONIZATION_SYNCHRONIZE, THREAD_PRIORITY_HIGHEST,
+                THREAD_PRIORITY_NORMAL, THREAD_PRIORITY_TIME_CRITICAL,
+            },
+        },
+        UI::{
+            Input::KeyboardAndMouse::{
+                GetLastInputInfo, MapVirtualKeyW, RegisterHotKey, SendInput, UnregisterHotKey,
+                keybd_event, HOT_KEY_MODIFIERS, INPUT, INPUT_0, INPUT_KEYBOARD, INPUT_MOUSE,
+                KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP, LASTINPUTINFO, MAPVK_VK_TO_VSC,
+                MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN, MOUSEEVENTF_MOVE,
+                MOUSEINPUT, VIRTUAL_KEY, VK_ESCAPE,
+            },
+            Shell::{
+                IShellLinkW,
+                PropertiesSystem::{IPropertyStore, PROPERTYKEY},
+                ShellExecuteW, ShellLink,
+            },
+            WindowsAndMessaging::{
+                BringWindowToTop, CallNextHookEx, CopyIcon, EnumWindows, FindWindowW, GetAncestor,
+                GetClassNameW, GetCursorPos, GetForegroundWindow, GetShellWindow, GetWindowLongW,
+                GetWindowRect, GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId,
+                IsIconic, IsWindow, IsWindowVisible, LoadCursorW, PostMessageW, SendMessageW,
+                SetCursorPos, SetForegroundWindow, SetSystemCursor, SetWindowPos, SetWindowsHookExW,
+                ShowWindow, SystemParametersInfoW, UnhookWindowsHookEx, WindowFromPoint, GA_ROOT,
+                GWL_EXSTYLE, GWL_STYLE, HCURSOR, HC_ACTION, HHOOK, HICON, HWND_NOTOPMOST,
+                HWND_TOPMOST, IDC_CROSS, KBDLLHOOKSTRUCT, MSLLHOOKSTRUCT, OCR_NORMAL,
+                SPI_SETCURSORS, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SW_HIDE, SW_RESTORE,
+                SW_SHOWNORMAL, WH_KEYBOARD_LL, WH_MOUSE_LL, WM_ACTIVATE, WM_APP, WM_HOTKEY,
+                WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_NCACTIVATE, WM_SETFOCUS,
+                WM_WTSSESSION_CHANGE, WSF_VISIBLE, WS_CAPTION, WS_CHILD, WS_DISABLED,
+                WS_EX_APPWINDOW, WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST,
+                WS_EX_TRANSPARENT, WS_MAXIMIZE, WS_MINIMIZE, WS_POPUP, WS_THICKFRAME,
+                WTS_CONSOLE_CONNECT, WTS_REMOTE_CONNECT,
+            },
+        },
+    },
+};
+
+const DEFAULT_WINDOW_TITLE: &str = "CounterSide";
+const REFRESH_INTERVAL_MS: u64 = 100;
+const MIN_INTERVAL_MS: u64 = 10;
+const WA_CLICKACTIVE: usize = 2;
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+const SESSION_HANDLER_ID: usize = 0x1_0000;
+const WM_CLICK_CAPTURED: u32 = WM_APP + 1;
+const WM_CLICK_CANCELLED: u32 = WM_APP + 2;
+const TOGGLE_HOTKEY_ID: i32 = 1;
+const MAX_TITLE_LEN: usize = 1 << 20;
+const AUDIT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+const CONSOLE_HOSTS: &[&str] = &["conhost.exe", "OpenConsole.exe"];
+const SHELL_WINDOW_CLASSES: &[&str] =
+    &["Shell_TrayWnd", "Shell_SecondaryTrayWnd", "Progman", "WorkerW"];
+/// Tooltips, menus, drop shadows and IME windows: never a sensible target,
+/// but titled and visible often enough to be picked by a loose rule.
+const DEFAULT_EXCLUDED_CLASSES: &[&str] =
+    &["tooltips_class32", "#32768", "SysShadow", "IME", "MSCTFIME UI"];
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "KeepActive - keep a target window in the foreground")]
+struct Args {
+    /// Run the application in console/CLI mode
+    #[arg(long)]
+    cli: bool,
+
+    /// Run without administrator rights instead of relaunching through the UAC prompt
+    #[arg(long)]
+    no_admin: bool,
+
+    /// Internal flag: run as a background worker for a single target
+    #[arg(long, hide = true)]
+    worker: bool,
+
+    /// Internal flag: named event the controller signals when the worker should exit
+    #[arg(long, hide = true, value_name = "NAME", requires = "worker")]
+    shutdown_event: Option<String>,
+
+    /// Internal flag: write a status line to stdout about once a second for the GUI
+    #[arg(long, hide = true, requires = "worker")]
+    report_status: bool,
+
+    /// Run a single worker in this console, printing what it resolves and sends each iteration
+    #[arg(long)]
+    worker_debug: bool,
+
+    /// Load window titles, executables and the interval from this TOML file; flags override it.
+    /// Defaults to %LOCALAPPDATA%\KeepActive\config.toml when that exists
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Window titles to target (repeatable; fallback list if processes are not found)
+    #[arg(short = 'w', long = "window", value_name = "TITLE", action = clap::ArgAction::Append)]
+    window: Vec<String>,
+
+    /// Read `|` in --window titles as separating alternatives tried in order, e.g. "New Title|Old Title"
+    #[arg(long)]
+    title_alternatives: bool,
+
+    /// Executable names to target (repeatable, e.g. notepad.exe)
+    #[arg(short = 'e', long = "exe", value_name = "NAME", action = clap::ArgAction::Append)]
+    exe: Vec<String>,
+
+    /// Process IDs to target directly, e.g. one of two instances of the same exe (repeatable)
+    #[arg(long, value_name = "PID", action = clap::ArgAction::Append)]
+    pid: Vec<u32>,
+
+    /// Executable that overrides every other rule while it has a window (added as an --exe if missing)
+    #[arg(long, value_name = "NAME")]
+    primary: Option<String>,
+
+    /// Start this executable whenever no process of its name is running (added as an --exe if missing)
+    #[arg(long, value_name = "PATH")]
+    launch: Option<PathBuf>,
+
+    /// Argument for the --launch executable (repeatable, in order)
+    #[arg(long, value_name = "ARG", requires = "launch", allow_hyphen_values = true)]
+    launch_args: Vec<String>,
+
+    /// Target the program a .lnk shortcut launches, including Store (UWP) app shortcuts (repeatable)
+    #[arg(long, value_name = "LNK", action = clap::ArgAction::Append)]
+    from_shortcut: Vec<PathBuf>,
+
+    /// Target the process started from exactly this executable, e.g. "C:\Games\Foo\game.exe" (repeatable)
+    #[arg(long, value_name = "PATH", action = clap::ArgAction::Append)]
+    exe_path: Vec<String>,
+
+    /// Target processes whose full image path contains SUBSTR, e.g. "SteamApps\common\MyGame" (repeatable)
+    #[arg(long, value_name = "SUBSTR", action = clap::ArgAction::Append)]
+    exe_path_contains: Vec<String>,
+
+    /// Target the first visible window whose title matches REGEX, checked after executables (repeatable)
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new, action = clap::ArgAction::Append)]
+    title_regex: Vec<Regex>,
+
+    /// While window A is in the foreground, also send the activation to window B, e.g. "editor.exe=>Reference" (repeatable)
+    #[arg(long, value_name = "A=>B", value_parser = MirrorRule::parse, action = clap::ArgAction::Append)]
+    mirror: Vec<MirrorRule>,
+
+    /// Target the window whose handle (decimal or 0x hex) another tool writes to PATH; re-read every iteration (repeatable)
+    #[arg(long, value_name = "PATH", action = clap::ArgAction::Append)]
+    hwnd_file: Vec<PathBuf>,
+
+    /// Window title substring that must hold together with the --match-exe at the same position (repeatable)
+    #[arg(long, value_name = "TITLE", requires = "match_exe", action = clap::ArgAction::Append)]
+    match_title: Vec<String>,
+
+    /// Executable whose window must also match the --match-title at the same position (repeatable)
+    #[arg(long, value_name = "NAME", requires = "match_title", action = clap::ArgAction::Append)]
+    match_exe: Vec<String>,
+
+    /// Target a visible window of this window class, optionally only one whose title contains TITLE, e.g. "UnityWndClass" (repeatable)
+    #[arg(long = "class", value_name = "NAME[=TITLE]", value_parser = ClassMatch::parse, action = clap::ArgAction::Append)]
+    window_class: Vec<ClassMatch>,
+
+    /// Skip activation while the workstation is locked
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = clap::ArgAction::Set)]
+    pause_when_locked: bool,
+
+    /// Milliseconds between activations (default 100); raise it to save battery
+    #[arg(long, value_name = "MS", conflicts_with = "adaptive_pacing")]
+    interval_ms: Option<u64>,
+
+    /// Vary every pause between iterations by up to MS milliseconds either way (default 0)
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    jitter_ms: u64,
+
+    /// Send N activations at the normal interval, then rest for --cooldown seconds, and repeat
+    #[arg(long, value_name = "N", requires = "cooldown", value_parser = clap::value_parser!(u32).range(1..))]
+    burst: Option<u32>,
+
+    /// Seconds to rest after each --burst of activations
+    #[arg(long, value_name = "SECONDS", requires = "burst")]
+    cooldown: Option<u64>,
+
+    /// Which process to target when several match an --exe or --exe-path-contains rule
+    #[arg(long, value_enum, default_value_t = ExeSelect::First)]
+    exe_select: ExeSelect,
+
+    /// Activate every window any rule matches, each once per tick, instead of just the first match (with --rotate, in turn)
+    #[arg(long, conflicts_with = "primary")]
+    all: bool,
+
+    /// Only pick process windows at least this large, e.g. 800x600 (skips toolbars and tooltips)
+    #[arg(long, value_name = "WxH", value_parser = parse_window_size)]
+    min_window_size: Option<(i32, i32)>,
+
+    /// Among one process's candidate windows, take the topmost or bottommost in the z-order
+    #[arg(long, value_enum, default_value_t = ZOrder::Top)]
+    z_order: ZOrder,
+
+    /// How --window titles are compared: the whole title, or a case-insensitive substring of it
+    #[arg(long, value_enum, default_value_t = MatchMode::Exact)]
+    match_mode: MatchMode,
+
+    /// Only pick process windows with (NAME) or without (!NAME) a style bit, e.g. "!WS_EX_TOOLWINDOW" (repeatable)
+    #[arg(long, value_name = "[!]STYLE", value_parser = StyleFilter::parse, action = clap::ArgAction::Append)]
+    style_filter: Vec<StyleFilter>,
+
+    /// Never pick a window of this class, whatever rule matched it (repeatable)
+    #[arg(long, value_name = "CLASS")]
+    exclude_class: Vec<String>,
+
+    /// Never pick a window whose title contains this text, ignoring case, whatever rule matched it (repeatable)
+    #[arg(long, value_name = "SUBSTRING")]
+    exclude: Vec<String>,
+
+    /// Allow tooltip, menu and IME windows, which are excluded by default; shell windows never are
+    #[arg(long)]
+    no_default_excludes: bool,
+
+    /// Only send when the target window or its foreground state changed since the last send
+    #[arg(long)]
+    coalesce: bool,
+
+    /// Scheduling priority for workers. `realtime` can starve input and other apps; use with care
+    #[arg(long, value_enum, default_value_t = Priority::Normal)]
+    priority: Priority,
+
+    /// Stop all workers after this long, e.g. 90s, 30m, 2h or 1h30m (CLI exits, GUI just stops)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    run_for: Option<Duration>,
+
+    /// Have each worker stop on its own SECONDS after it starts; 0 (the default) never stops
+    #[arg(long, value_name = "SECONDS")]
+    max_duration: Option<u64>,
+
+    /// Have each worker wait SECONDS before its first iteration, e.g. while a game starts up
+    #[arg(long, value_name = "SECONDS")]
+    start_delay: Option<u64>,
+
+    /// Start a worker again when it crashes or is killed while activation is running
+    #[arg(long)]
+    supervise: bool,
+
+    /// Give each resolvable target the foreground in turn for this long, e.g. 10s (one worker for all rules)
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        conflicts_with_all = [
+            "burst",
+            "coalesce",
+            "double_tap",
+            "only_when_background",
+            "only_when_playing",
+            "primary",
+        ]
+    )]
+    rotate: Option<Duration>,
+
+    /// Let --rotate take the foreground at most N times per minute; past that it only sends the message
+    #[arg(long, value_name = "N", requires = "rotate")]
+    max_steals_per_min: Option<u32>,
+
+    /// If the target is still not in the foreground after K sends, escalate to stronger methods
+    #[arg(
+        long,
+        value_name = "K",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["only_when_background", "rotate"]
+    )]
+    escalate: Option<u32>,
+
+    /// Find out per target which --escalate method gets it into the foreground, then keep using that one
+    #[arg(long, conflicts_with_all = ["escalate", "only_when_background", "rotate"])]
+    auto_method: bool,
+
+    /// How to activate the target: a WM_ACTIVATE message, or taking the real foreground
+    #[arg(long, value_enum, default_value_t = ActivateMethod::Message)]
+    activate_method: ActivateMethod,
+
+    /// Post WM_ACTIVATE instead of sending it, so the worker never waits on the target or touches the foreground
+    #[arg(long, conflicts_with_all = ["escalate", "auto_method", "rotate", "adaptive_pacing"])]
+    no_steal: bool,
+
+    /// With --no-steal, wrap the WM_ACTIVATE in WM_NCACTIVATE and WM_SETFOCUS like a real activation
+    #[arg(long, requires = "no_steal")]
+    focus_messages: bool,
+
+    /// Send every activation twice, MS milliseconds apart, for apps that ignore the first one
+    #[arg(long, value_name = "MS")]
+    double_tap: Option<u64>,
+
+    /// Only touch the target while it is NOT the foreground window, so it is left alone while in use
+    #[arg(long)]
+    only_when_background: bool,
+
+    /// Only activate while the target (or a child process) has an active audio session. Needs the `audio` feature
+    #[arg(long)]
+    only_when_playing: bool,
+
+    /// Stretch the gap between activations while the target answers slowly, and shrink it back once it recovers
+    #[arg(long, conflicts_with = "rotate")]
+    adaptive_pacing: bool,
+
+    /// Shortest gap --adaptive-pacing uses, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = REFRESH_INTERVAL_MS, requires = "adaptive_pacing")]
+    pace_min: u64,
+
+    /// Longest gap --adaptive-pacing backs off to, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 5000, requires = "adaptive_pacing")]
+    pace_max: u64,
+
+    /// Leave the target alone while its current title matches REGEX, e.g. "Updating"
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+    suppress_while_title: Option<Regex>,
+
+    /// Hold off for MS milliseconds after any keyboard or mouse input, resuming once input quiets
+    #[arg(long, value_name = "MS")]
+    input_grace: Option<u64>,
+
+    /// Only activate from this local time on, e.g. 22:00 (needs --active-to)
+    #[arg(long, value_name = "HH:MM", value_parser = parse_clock_time, requires = "active_to")]
+    active_from: Option<u32>,
+
+    /// Stop activating at this local time, e.g. 06:00; may be earlier than --active-from
+    #[arg(long, value_name = "HH:MM", value_parser = parse_clock_time, requires = "active_from")]
+    active_to: Option<u32>,
+
+    /// While the target is in the foreground, nudge the pointer by one pixel and back once a second
+    #[arg(long, conflicts_with_all = ["input_grace", "only_when_background"])]
+    jiggle_cursor: bool,
+
+    /// Also run a worker that nudges the pointer by one pixel and back every interval, whatever the targets do
+    #[arg(long, conflicts_with = "input_grace")]
+    jiggle: bool,
+
+    /// Only run the --jiggle worker and activate no windows
+    #[arg(long, requires = "jiggle", conflicts_with = "mirror")]
+    jiggle_only: bool,
+
+    /// Also press VK (F15 if no key is given) in the target about once a second, e.g. F15, F24 or 0x7E
+    #[arg(
+        long,
+        value_name = "VK",
+        num_args = 0..=1,
+        default_missing_value = "F15",
+        value_parser = parse_virtual_key
+    )]
+    keepalive_key: Option<u32>,
+
+    /// How --keepalive-key reaches the target: posted to its window, or typed with SendInput while it has focus
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = KeyMethod::Post,
+        requires = "keepalive_key",
+        conflicts_with = "input_grace"
+    )]
+    keepalive_key_method: KeyMethod,
+
+    /// Flag activations of minimized targets as minimized so they stay in the taskbar
+    #[arg(long)]
+    keep_minimized: bool,
+
+    /// Restore a minimized target before activating it
+    #[arg(long, conflicts_with = "keep_minimized")]
+    restore: bool,
+
+    /// Keep the system awake and the display on while the worker runs
+    #[arg(long)]
+    prevent_sleep: bool,
+
+    /// Find the targets and log what would be activated, but leave the windows alone
+    #[arg(long, conflicts_with_all = ["rotate", "mirror"])]
+    dry_run: bool,
+
+    /// Append one CSV row per worker iteration (time, target, window, action, result) to PATH
+    #[arg(long, value_name = "PATH")]
+    audit_csv: Option<PathBuf>,
+
+    /// Append timestamped worker events (start, found, lost, activation, stop) to PATH, one file per worker
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Write a minidump to %LOCALAPPDATA%\KeepActive\CrashDumps if the GUI, CLI or a worker crashes
+    #[arg(long)]
+    crash_dumps: bool,
+
+    /// Use this .ico file for the GUI window instead of the built-in icon
+    #[arg(long, value_name = "PATH")]
+    icon: Option<PathBuf>,
+
+    /// GUI only: system-wide hotkey that starts or stops activation, e.g. Ctrl+Shift+F9, or "none"
+    #[arg(long, value_name = "KEYS", default_value = "Ctrl+Alt+K", value_parser = parse_hotkey)]
+    hotkey: Hotkey,
+
+    /// Show what each target resolves to and ask for confirmation before starting
+    #[arg(long)]
+    preview: bool,
+
+    /// Don't print the session summary when the CLI stops or exits
+    #[arg(long, conflicts_with = "json")]
+    quiet: bool,
+
+    /// Print the session summary as a single line of JSON
+    #[arg(long)]
+    json: bool,
+
+    /// Start KeepActive at logon with the other arguments given here, then exit
+    #[arg(long, conflicts_with = "uninstall_autostart")]
+    install_autostart: bool,
+
+    /// Stop starting KeepActive at logon, then exit
+    #[arg(long)]
+    uninstall_autostart: bool,
+
+    /// Write a diagnostics report (config, OS info, target resolution, window list) to PATH and exit
+    #[arg(long, value_name = "PATH")]
+    diagnostics: Option<PathBuf>,
+
+    /// Replace window titles in the diagnostics report with hashes
+    #[arg(long, requires = "diagnostics")]
+    redact_titles: bool,
+
+    /// Start the workers in the logged-on user's session ID instead of this one. Needs SYSTEM
+    #[arg(long, value_name = "ID", requires = "cli")]
+    session: Option<u32>,
+
+    /// Resolve the targets once, print the result and exit 0 if one was found, 1 otherwise
+    #[arg(long, conflicts_with = "diagnostics")]
+    check_target: bool,
+
+    /// Print every window the rules match right now (handle, PID, class, title) and exit
+    #[arg(long, conflicts_with_all = ["diagnostics", "check_target"])]
+    list: bool,
+}
+
+/// The TOML file read by `--config`. Every key is optional; unknown keys are
+/// rejected so a typo does not go unnoticed.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    window_titles: Vec<String>,
+    process_names: Vec<String>,
+    interval_ms: Option<u64>,
+}
+
+impl ConfigFile {
+    /// Reads `explicit`, or the default file when none was given. Only a
+    /// missing default file is not an error.
+    fn load(explicit: Option<&Path>) -> Result<Option<Self>> {
+        let path = explicit.map_or_else(|| app_data_dir().join("config.toml"), Path::to_path_buf);
+        Self::read(&path, explicit.is_some())
+    }
+
+    /// Parses the file at `path`; a missing file is only an error when it was
+    /// `required`.
+    fn read(path: &Path, required: bool) -> Result<Option<Self>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if !required && error.kind() == io::ErrorKind::NotFound => {
+                return Ok(None);
+            }
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("failed to read config file {}", path.display()));
+            }
+        };
+        toml::from_str(&text)
+            .map(Some)
+            .with_context(|| format!("failed to parse config file {}", path.display()))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct AppConfig {
+    window_titles: Vec<String>,
+    process_names: Vec<String>,
+    pids: Vec<u32>,
+    exe_paths: Vec<String>,
+    path_fragments: Vec<String>,
+    title_regexes: Vec<Regex>,
+    title_exe_pairs: Vec<TitleExeMatch>,
+    class_matches: Vec<ClassMatch>,
+    mirrors: Vec<MirrorRule>,
+    hwnd_files: Vec<PathBuf>,
+    options: WorkerOptions,
+    preview: bool,
+    quiet: bool,
+    json: bool,
+    run_for: Option<Duration>,
+    icon: Option<PathBuf>,
+    hotkey: Hotkey,
+    session: Option<u32>,
+}
+
+impl AppConfig {
+    fn from_args(args: &Args) -> Self {
+        let mut window_titles = normalize_list(args.window.clone());
+        if window_titles.is_empty() {
+            window_titles.push(DEFAULT_WINDOW_TITLE.to_string());
+        }
+        let mut process_names = normalize_list(args.exe.clone());
+        // Workers get their exact rule list from the controller.
+        if !args.worker {
+            add_primary(&mut process_names, args.primary.as_deref());
+            add_launch_target(&mut process_names, args.launch.as_deref());
+        }
+        Self {
+            window_titles,
+            process_names,
+            pids: dedup_pids(&args.pid),
+            exe_paths: normalize_list(
+                args.exe_path.iter().map(|path| normalize_exe_path(path)).collect(),
+            ),
+            path_fragments: normalize_list(args.exe_path_contains.clone()),
+            title_regexes: args.title_regex.clone(),
+            title_exe_pairs: args
+                .match_title
+                .iter()
+                .zip(&args.match_exe)
+                .map(|(title, exe)| TitleExeMatch {
+                    title: title.trim().to_string(),
+                    exe: exe.trim().to_string(),
+                })
+                .collect(),
+            class_matches: args.window_class.clone(),
+            mirrors: args.mirror.clone(),
+            hwnd_files: args.hwnd_file.clone(),
+            options: WorkerOptions::from_args(args),
+            preview: args.preview,
+            quiet: args.quiet,
+            json: args.json,
+            run_for: args.run_for,
+            icon: args.icon.clone(),
+            hotkey: args.hotkey,
+            session: args.session,
+        }
+    }
+
+    /// `from_args` plus the config file: `--config`, or the default file if
+    /// it exists. Each setting in the file only applies when the command line
+    /// leaves it unset. Workers get everything from the controller, so they
+    /// never read a file.
+    fn from_config_file(args: &Args) -> Result<Self> {
+        let mut config = Self::from_args(args);
+        if args.worker {
+            return Ok(config);
+        }
+        if let Some(file) = ConfigFile::load(args.config.as_deref())? {
+            config.merge_file(args, file);
+        }
+        Ok(config)
+    }
+
+    /// Takes each setting from `file` that `args` leaves unset.
+    fn merge_file(&mut self, args: &Args, file: ConfigFile) {
+        let window_titles = normalize_list(file.window_titles);
+        if normalize_list(args.window.clone()).is_empty() && !window_titles.is_empty() {
+            self.window_titles = window_titles;
+        }
+        if normalize_list(args.exe.clone()).is_empty() {
+            self.process_names = normalize_list(file.process_names);
+            add_primary(&mut self.process_names, args.primary.as_deref());
+            add_launch_target(&mut self.process_names, args.launch.as_deref());
+        }
+        if args.interval_ms.is_none()
+            && let Some(interval_ms) = file.interval_ms
+        {
+            self.options.interval_ms = interval_ms;
+        }
+    }
+
+    fn resolved(&self) -> ResolvedConfig {
+        ResolvedConfig {
+            pids: dedup_pids(&self.pids),
+            exe_paths: normalize_list(self.exe_paths.clone()),
+            path_fragments: normalize_list(self.path_fragments.clone()),
+            title_regexes: self.title_regexes.clone(),
+            title_exe_pairs: self.title_exe_pairs.clone(),
+            class_matches: self.class_matches.clone(),
+            mirrors: self.mirrors.clone(),
+            hwnd_files: self.hwnd_files.clone(),
+            ..ResolvedConfig::from_lists(
+                self.window_titles.clone(),
+                self.process_names.clone(),
+                self.options.clone(),
+            )
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum Priority {
+    Normal,
+    High,
+    Realtime,
+}
+
+impl Priority {
+    fn name(self) -> &'static str {
+        match self {
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Realtime => "realtime",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum ExeSelect {
+    First,
+    All,
+    LargestWindow,
+    Newest,
+}
+
+impl ExeSelect {
+    fn name(self) -> &'static str {
+        match self {
+            ExeSelect::First => "first",
+            ExeSelect::All => "all",
+            ExeSelect::LargestWindow => "largest-window",
+            ExeSelect::Newest => "newest",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum ZOrder {
+    Top,
+    Bottom,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum MatchMode {
+    Exact,
+    Contains,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum ActivateMethod {
+    Message,
+    Foreground,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum KeyMethod {
+    Post,
+    Input,
+}
+
+/// Per-worker behaviour switches. The controller forwards these to every
+/// worker it spawns, so each field needs a matching `push_args` entry.
+#[derive(Clone, Debug)]
+struct WorkerOptions {
+    pause_when_locked: bool,
+    interval_ms: u64,
+    jitter_ms: u64,
+    max_duration: Option<Duration>,
+    start_delay: Option<Duration>,
+    supervise: bool,
+    no_admin: bool,
+    title_alternatives: bool,
+    burst: Option<u32>,
+    cooldown_secs: u64,
+    style_filters: Vec<StyleFilter>,
+    coalesce: bool,
+    priority: Priority,
+    double_tap_ms: Option<u64>,
+    only_when_background: bool,
+    only_when_playing: bool,
+    keep_minimized: bool,
+    restore: bool,
+    prevent_sleep: bool,
+    dry_run: bool,
+    exe_select: ExeSelect,
+    z_order: ZOrder,
+    audit_csv: Option<PathBuf>,
+    log_file: Option<PathBuf>,
+    launch: Option<LaunchTarget>,
+    crash_dumps: bool,
+    rotate: Option<Duration>,
+    primary: Option<String>,
+    input_grace_ms: Option<u64>,
+    schedule: Option<Schedule>,
+    max_steals_per_min: Option<u32>,
+    min_window_size: Option<(i32, i32)>,
+    escalate: Option<u32>,
+    jiggle_cursor: bool,
+    /// Set on the `--jiggle` worker only; see `KeepAliveController::start`.
+    jiggle: bool,
+    jiggle_only: bool,
+    keepalive_key: Option<(u32, KeyMethod)>,
+    suppress_while_title: Option<Regex>,
+    pacing: Option<(u64, u64)>,
+    exclude_classes: Vec<String>,
+    /// Lowercased `--exclude` substrings.
+    exclude_titles: Vec<String>,
+    default_excludes: bool,
+    auto_method: bool,
+    match_mode: MatchMode,
+    all_windows: bool,
+    activate_method: ActivateMethod,
+    no_steal: bool,
+    focus_messages: bool,
+}
+
+impl WorkerOptions {
+    fn from_args(args: &Args) -> Self {
+        Self {
+            pause_when_locked: args.pause_when_locked,
+            interval_ms: args.interval_ms.unwrap_or(REFRESH_INTERVAL_MS),
+            jitter_ms: args.jitter_ms,
+            max_duration: args.max_duration.filter(|&secs| secs > 0).map(Duration::from_secs),
+            start_delay: args.start_delay.filter(|&secs| secs > 0).map(Duration::from_secs),
+            supervise: args.supervise,
+            no_admin: args.no_admin,
+            title_alternatives: args.title_alternatives,
+            burst: args.burst,
+            cooldown_secs: args.cooldown.unwrap_or(0),
+            style_filters: args.style_filter.clone(),
+            coalesce: args.coalesce,
+            priority: args.priority,
+            double_tap_ms: args.double_tap,
+            only_when_background: args.only_when_background,
+            only_when_playing: args.only_when_playing,
+            keep_minimized: args.keep_minimized,
+            restore: args.restore,
+            prevent_sleep: args.prevent_sleep,
+            dry_run: args.dry_run,
+            exe_select: args.exe_select,
+            z_order: args.z_order,
+            audit_csv: args.audit_csv.clone(),
+            log_file: args.log_file.clone(),
+            launch: args.launch.clone().map(|path| LaunchTarget {
+                path,
+                args: args.launch_args.clone(),
+            }),
+            crash_dumps: args.crash_dumps,
+            rotate: args.rotate,
+            primary: args.primary.as_ref().map(|name| name.trim().to_string()),
+            input_grace_ms: args.input_grace,
+            schedule: args
+                .active_from
+                .zip(args.active_to)
+                .map(|(from, to)| Schedule { from, to }),
+            max_steals_per_min: args.max_steals_per_min,
+            min_window_size: args.min_window_size,
+            escalate: args.escalate,
+            jiggle_cursor: args.jiggle_cursor,
+            jiggle: args.jiggle,
+            jiggle_only: args.jiggle_only,
+            keepalive_key: args.keepalive_key.map(|vk| (vk, args.keepalive_key_method)),
+            suppress_while_title: args.suppress_while_title.clone(),
+            pacing: args.adaptive_pacing.then_some((args.pace_min, args.pace_max)),
+            exclude_classes: normalize_list(args.exclude_class.clone()),
+            exclude_titles: normalize_list(args.exclude.clone())
+                .iter()
+                .map(|text| text.to_lowercase())
+                .collect(),
+            default_excludes: !args.no_default_excludes,
+            auto_method: args.auto_method,
+            match_mode: args.match_mode,
+            all_windows: args.all,
+            activate_method: args.activate_method,
+            no_steal: args.no_steal,
+            focus_messages: args.focus_messages,
+        }
+    }
+
+    fn push_args(&self, cmd: &mut Command) {
+        if !self.pause_when_locked {
+            cmd.arg("--pause-when-locked").arg("false");
+        }
+        if self.interval_ms != REFRESH_INTERVAL_MS {
+            cmd.arg("--interval-ms").arg(self.interval_ms.to_string());
+        }
+        if self.jitter_ms != 0 {
+            cmd.arg("--jitter-ms").arg(self.jitter_ms.to_string());
+        }
+        // An unelevated controller's workers must not prompt on their own.
+        if self.no_admin {
+            cmd.arg("--no-admin");
+        }
+        if self.title_alternatives {
+            cmd.arg("--title-alternatives");
+        }
+        if let Some(limit) = self.max_duration {
+            cmd.arg("--max-duration").arg(limit.as_secs().to_string());
+        }
+        if let Some(delay) = self.start_delay {
+            cmd.arg("--start-delay").arg(delay.as_secs().to_string());
+        }
+        if let Some(burst) = self.burst {
+            cmd.arg("--burst").arg(burst.to_string());
+            cmd.arg("--cooldown").arg(self.cooldown_secs.to_string());
+        }
+        for filter in &self.style_filters {
+            cmd.arg("--style-filter").arg(filter.to_string());
+        }
+        if self.coalesce {
+            cmd.arg("--coalesce");
+        }
+        if self.priority != Priority::Normal {
+            cmd.arg("--priority").arg(self.priority.name());
+        }
+        if let Some(delay) = self.double_tap_ms {
+            cmd.arg("--double-tap").arg(delay.to_string());
+        }
+        if self.only_when_background {
+            cmd.arg("--only-when-background");
+        }
+        if self.only_when_playing {
+            cmd.arg("--only-when-playing");
+        }
+        if self.keep_minimized {
+            cmd.arg("--keep-minimized");
+        }
+        if self.restore {
+            cmd.arg("--restore");
+        }
+        if self.prevent_sleep {
+            cmd.arg("--prevent-sleep");
+        }
+        if self.dry_run {
+            cmd.arg("--dry-run");
+        }
+        if self.exe_select != ExeSelect::First {
+            cmd.arg("--exe-select").arg(self.exe_select.name());
+        }
+        if self.z_order == ZOrder::Bottom {
+            cmd.arg("--z-order").arg("bottom");
+        }
+        if let Some(path) = &self.audit_csv {
+            cmd.arg("--audit-csv").arg(path);
+        }
+        // --log-file is added by KeepAliveController::spawn, one file per
+        // worker, --supervise only concerns the controller, and --jiggle only
+        // goes to the jiggle worker.
+        if let Some(launch) = &self.launch {
+            cmd.arg("--launch").arg(&launch.path);
+            for arg in &launch.args {
+                // The = form keeps an argument such as -windowed from
+                // reading as a flag of its own.
+                cmd.arg(format!("--launch-args={}", arg));
+            }
+        }
+        if self.crash_dumps {
+            cmd.arg("--crash-dumps");
+        }
+        if let Some(slice) = self.rotate {
+            cmd.arg("--rotate").arg(format!("{}s", slice.as_secs()));
+        }
+        if let Some(primary) = &self.primary {
+            cmd.arg("--primary").arg(primary);
+        }
+        if let Some(grace) = self.input_grace_ms {
+            cmd.arg("--input-grace").arg(grace.to_string());
+        }
+        if let Some(schedule) = self.schedule {
+            cmd.arg("--active-from").arg(clock_time(schedule.from));
+            cmd.arg("--active-to").arg(clock_time(schedule.to));
+        }
+        if let Some(limit) = self.max_steals_per_min {
+            cmd.arg("--max-steals-per-min").arg(limit.to_string());
+        }
+        if let Some((width, height)) = self.min_window_size {
+            cmd.arg("--min-window-size").arg(format!("{}x{}", width, height));
+        }
+        if let Some(attempts) = self.escalate {
+            cmd.arg("--escalate").arg(attempts.to_string());
+        }
+        if let Some((vk, method)) = self.keepalive_key {
+            // The `=` form keeps the optional value attached to its flag.
+            cmd.arg(format!("--keepalive-key=0x{:02X}", vk));
+            if method == KeyMethod::Input {
+                cmd.arg("--keepalive-key-method").arg("input");
+            }
+        }
+        if self.jiggle_cursor {
+            cmd.arg("--jiggle-cursor");
+        }
+        if let Some(pattern) = &self.suppress_while_title {
+            cmd.arg("--suppress-while-title").arg(pattern.as_str());
+        }
+        if let Some((min, max)) = self.pacing {
+            cmd.arg("--adaptive-pacing");
+            cmd.arg("--pace-min").arg(min.to_string());
+            cmd.arg("--pace-max").arg(max.to_string());
+        }
+        for class in &self.exclude_classes {
+            cmd.arg("--exclude-class").arg(class);
+        }
+        for text in &self.exclude_titles {
+            cmd.arg("--exclude").arg(text);
+        }
+        if !self.default_excludes {
+            cmd.arg("--no-default-excludes");
+        }
+        if self.auto_method {
+            cmd.arg("--auto-method");
+        }
+        if self.match_mode == MatchMode::Contains {
+            cmd.arg("--match-mode").arg("contains");
+        }
+        if self.all_windows {
+            cmd.arg("--all");
+        }
+        if self.activate_method == ActivateMethod::Foreground {
+            cmd.arg("--activate-method").arg("foreground");
+        }
+        if self.no_steal {
+            cmd.arg("--no-steal");
+        }
+        if self.focus_messages {
+            cmd.arg("--focus-messages");
+        }
+    }
+
+    /// Whether a loop started at `started` has used up `--max-duration`.
+    fn expired(&self, started: Instant) -> bool {
+        self.max_duration.is_some_and(|limit| started.elapsed() >= limit)
+    }
+
+    /// These options with the interval of `rule`'s `@MS` suffix, if it had one.
+    fn for_rule(&self, target_intervals: &HashMap<String, u64>, rule: &str) -> Self {
+        let interval_ms = target_intervals.get(&rule.to_lowercase()).copied();
+        Self {
+            interval_ms: interval_ms.unwrap_or(self.interval_ms),
+            ..self.clone()
+        }
+    }
+
+    /// The pause between iterations of the worker loops (`--interval-ms`).
+    fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+}
+
+/// Style bits understood by `--style-filter`: (name, extended style, bits).
+const STYLE_NAMES: &[(&str, bool, u32)] = &[
+    ("WS_POPUP", false, WS_POPUP.0),
+    ("WS_CHILD", false, WS_CHILD.0),
+    ("WS_CAPTION", false, WS_CAPTION.0),
+    ("WS_THICKFRAME", false, WS_THICKFRAME.0),
+    ("WS_MINIMIZE", false, WS_MINIMIZE.0),
+    ("WS_MAXIMIZE", false, WS_MAXIMIZE.0),
+    ("WS_DISABLED", false, WS_DISABLED.0),
+    ("WS_EX_TOOLWINDOW", true, WS_EX_TOOLWINDOW.0),
+    ("WS_EX_APPWINDOW", true, WS_EX_APPWINDOW.0),
+    ("WS_EX_TOPMOST", true, WS_EX_TOPMOST.0),
+    ("WS_EX_LAYERED", true, WS_EX_LAYERED.0),
+    ("WS_EX_TRANSPARENT", true, WS_EX_TRANSPARENT.0),
+    ("WS_EX_NOACTIVATE", true, WS_EX_NOACTIVATE.0),
+];
+
+#[derive(Clone, Copy, Debug)]
+struct StyleFilter {
+    name: &'static str,
+    extended: bool,
+    bits: u32,
+    exclude: bool,
+}
+
+impl StyleFilter {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let (exclude, name) = match spec.strip_prefix('!') {
+            Some(rest) => (true, rest.trim()),
+            None => (false, spec),
+        };
+        STYLE_NAMES
+            .iter()
+            .find(|(known, _, _)| known.eq_ignore_ascii_case(name))
+            .map(|&(name, extended, bits)| Self {
+                name,
+                extended,
+                bits,
+                exclude,
+            })
+            .ok_or_else(|| {
+                let known: Vec<&str> = STYLE_NAMES.iter().map(|(name, _, _)| *name).collect();
+                format!("unknown style '{}' (expected one of {})", name, known.join(", "))
+            })
+    }
+
+    fn matches(&self, style: u32, ex_style: u32) -> bool {
+        let value = if self.extended { ex_style } else { style };
+        (value & self.bits == self.bits) != self.exclude
+    }
+}
+
+impl fmt::Display for StyleFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.exclude {
+            f.write_str("!")?;
+        }
+        f.write_str(self.name)
+    }
+}
+
+/// Everything a worker needs to find and activate its windows. Build one
+/// with [`ResolvedConfig::from_args`].
+#[derive(Clone, Debug)]
+pub struct ResolvedConfig {
+    window_titles: Vec<String>,
+    process_names: Vec<String>,
+    pids: Vec<u32>,
+    exe_paths: Vec<String>,
+    path_fragments: Vec<String>,
+    title_regexes: Vec<Regex>,
+    title_exe_pairs: Vec<TitleExeMatch>,
+    class_matches: Vec<ClassMatch>,
+    mirrors: Vec<MirrorRule>,
+    hwnd_files: Vec<PathBuf>,
+    /// `@MS` suffixes from `--window`/`--exe` entries, keyed by the
+    /// lowercased entry without its suffix.
+    target_intervals: HashMap<String, u64>,
+    options: WorkerOptions,
+}
+
+/// A `--match-title`/`--match-exe` pair: the window must belong to `exe` and
+/// its title must contain `title` (case-insensitive).
+#[derive(Clone, Debug)]
+struct TitleExeMatch {
+    title: String,
+    exe: String,
+}
+
+/// A `--class NAME[=TITLE]` rule: the window class must be `class` and, when
+/// `title` is given, the window title must contain it (both case-insensitive).
+#[derive(Clone, Debug)]
+struct ClassMatch {
+    class: String,
+    title: Option<String>,
+}
+
+impl ClassMatch {
+    fn parse(value: &str) -> Result<Self, String> {
+        let (class, title) = match value.split_once('=') {
+            Some((class, title)) => (class.trim(), Some(title.trim())),
+            None => (value.trim(), None),
+        };
+        if class.is_empty() || title.is_some_and(str::is_empty) {
+            return Err(format!("expected NAME or NAME=TITLE, got '{}'", value));
+        }
+        Ok(Self {
+            class: class.to_string(),
+            title: title.map(str::to_string),
+        })
+    }
+}
+
+impl fmt::Display for ClassMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.title {
+            Some(title) => write!(f, "{}={}", self.class, title),
+            None => f.write_str(&self.class),
+        }
+    }
+}
+
+/// A `--mirror A=>B` rule. Each side names an executable when it ends in
+/// `.exe` and a window title (with `|` alternatives under
+/// `--title-alternatives`) otherwise.
+#[derive(Clone, Debug)]
+struct MirrorRule {
+    source: String,
+    target: String,
+}
+
+impl MirrorRule {
+    fn parse(value: &str) -> Result<Self, String> {
+        let (source, target) = value
+            .split_once("=>")
+            .ok_or_else(|| format!("expected A=>B, got '{}'", value))?;
+        let (source, target) = (source.trim(), target.trim());
+        if source.is_empty() || target.is_empty() {
+            return Err(format!("both sides of '{}' must name a window", value));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            target: target.to_string(),
+        })
+    }
+
+    fn resolve(side: &str, options: &WorkerOptions) -> Option<HWND> {
+        let scan = TargetScan::default();
+        if side.to_lowercase().ends_with(".exe") {
+            select_process_windows(&scan, scan.pids_named(side), options)
+                .first()
+                .map(|&(hwnd, _)| hwnd)
+        } else {
+            title_candidates(side, options.title_alternatives)
+                .iter()
+                .find_map(|title| find_window_by_title(&scan, title, options))
+        }
+    }
+}
+
+impl fmt::Display for MirrorRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}=>{}", self.source, self.target)
+    }
+}
+
+impl ResolvedConfig {
+    /// Builds a configuration from the binary's own flags, for example
+    /// `["--exe", "game.exe", "--interval-ms", "250"]`. The config file is
+    /// not read, and the result is validated as the binary would.
+    pub fn from_args<I, T>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString>,
+    {
+        let program = std::iter::once(OsString::from("KeepActive"));
+        let args = Args::try_parse_from(program.chain(args.into_iter().map(Into::into)))?;
+        check_title_exe_pairs(&args)?;
+        let mut config = AppConfig::from_args(&args);
+        for shortcut in &args.from_shortcut {
+            config.path_fragments.push(resolve_shortcut(shortcut)?);
+        }
+        let config = config.resolved();
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_lists(
+        window_titles: Vec<String>,
+        process_names: Vec<String>,
+        options: WorkerOptions,
+    ) -> Self {
+        let mut target_intervals = HashMap::new();
+        let mut window_titles =
+            normalize_list(take_intervals(window_titles, &mut target_intervals));
+        if window_titles.is_empty() {
+            window_titles.push(DEFAULT_WINDOW_TITLE.to_string());
+        }
+        let process_names = normalize_list(take_intervals(process_names, &mut target_intervals));
+        Self {
+            window_titles,
+            process_names,
+            pids: Vec::new(),
+            exe_paths: Vec::new(),
+            path_fragments: Vec::new(),
+            title_regexes: Vec::new(),
+            title_exe_pairs: Vec::new(),
+            class_matches: Vec::new(),
+            mirrors: Vec::new(),
+            hwnd_files: Vec::new(),
+            target_intervals,
+            options,
+        }
+    }
+
+    /// Checks for option combinations that would silently do nothing or
+    /// contradict each other, naming the offending flag in each message.
+    fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+        for entry in &self.window_titles {
+            if title_candidates(entry, self.options.title_alternatives).is_empty() {
+                problems.push(format!("--window '{}' contains no titles", entry));
+            }
+        }
+        for pair in &self.title_exe_pairs {
+            if pair.title.is_empty() || pair.exe.is_empty() {
+                problems.push(format!(
+                    "--match-title '{}' --match-exe '{}' needs both values",
+                    pair.title, pair.exe
+                ));
+            }
+        }
+        if self.options.interval_ms < MIN_INTERVAL_MS {
+            problems.push(format!("--interval-ms must be at least {} ms", MIN_INTERVAL_MS));
+        }
+        let mut target_intervals: Vec<_> = self.target_intervals.iter().collect();
+        target_intervals.sort();
+        for (entry, &interval_ms) in target_intervals {
+            if interval_ms < MIN_INTERVAL_MS {
+                problems.push(format!(
+                    "'{}@{}' must use an interval of at least {} ms",
+                    entry, interval_ms, MIN_INTERVAL_MS
+                ));
+            }
+        }
+        if !self.target_intervals.is_empty() && self.options.pacing.is_some() {
+            problems.push(
+                "per-target @MS intervals cannot be combined with --adaptive-pacing".to_string(),
+            );
+        }
+        if !self.target_intervals.is_empty()
+            && (self.options.rotate.is_some() || self.options.all_windows)
+        {
+            // One worker runs every rule there, at a single interval.
+            problems.push(
+                "per-target @MS intervals cannot be combined with --rotate or --all".to_string(),
+            );
+        }
+        if let Some(schedule) = self.options.schedule
+            && schedule.from == schedule.to
+        {
+            problems.push("--active-from and --active-to must be different times".to_string());
+        }
+        if self.options.burst.is_some() && self.options.cooldown_secs == 0 {
+            problems.push("--cooldown must be at least 1 second when --burst is set".to_string());
+        }
+        if cfg!(not(feature = "audio")) && self.options.only_when_playing {
+            problems.push("--only-when-playing needs a build with `--features audio`".to_string());
+        }
+        if self.options.rotate.is_some_and(|slice| slice.is_zero()) {
+            problems.push("--rotate must be at least 1 second".to_string());
+        }
+        let several = if self.options.all_windows {
+            Some("--all")
+        } else if self.options.exe_select == ExeSelect::All {
+            Some("--exe-select all")
+        } else {
+            None
+        };
+        if let Some(several) = several {
+            let flag = if self.options.escalate.is_some() {
+                Some("--escalate")
+            } else if self.options.auto_method {
+                Some("--auto-method")
+            } else if self.options.activate_method == ActivateMethod::Foreground {
+                Some("--activate-method foreground")
+            } else {
+                None
+            };
+            if let Some(flag) = flag {
+                problems.push(format!(
+                    "{} cannot put several windows in the foreground ({})",
+                    flag, several
+                ));
+            }
+        }
+        // The default is indistinguishable from an explicit `message`, so the
+        // clash is checked here rather than by clap.
+        if self.options.activate_method == ActivateMethod::Foreground {
+            let clash = [
+                (self.options.escalate.is_some(), "--escalate"),
+                (self.options.auto_method, "--auto-method"),
+                (self.options.only_when_background, "--only-when-background"),
+                (self.options.rotate.is_some(), "--rotate"),
+                (self.options.no_steal, "--no-steal"),
+            ];
+            for (_, flag) in clash.iter().filter(|(set, _)| *set) {
+                problems
+                    .push(format!("--activate-method foreground cannot be combined with {}", flag));
+            }
+        }
+        if let Some((min, max)) = self.options.pacing {
+            if min == 0 {
+                problems.push("--pace-min must be greater than 0 ms".to_string());
+            }
+            if min > max {
+                problems.push(format!("--pace-min {}ms is above --pace-max {}ms", min, max));
+            }
+        }
+        if self.options.double_tap_ms == Some(0) {
+            problems.push("--double-tap must be greater than 0 ms".to_string());
+        }
+        for (index, filter) in self.options.style_filters.iter().enumerate() {
+            let contradicted = self.options.style_filters[..index].iter().any(|other| {
+                other.name == filter.name && other.exclude != filter.exclude
+            });
+            if contradicted {
+                problems.push(format!(
+                    "--style-filter {} and !{} can never both hold",
+                    filter.name, filter.name
+                ));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("invalid configuration:\n  - {}", problems.join("\n  - ")))
+        }
+    }
+}
+
+struct KeepAliveController {
+    children: Vec<Worker>,
+    session: Option<SessionInfo>,
+    user_session: Option<u32>,
+    status: Option<Arc<StatusBoard>>,
+    log_file: Option<PathBuf>,
+    supervise: bool,
+    restarts: usize,
+}
+
+struct SessionInfo {
+    started_at: Instant,
+    /// Each worker's ID and what it targets.
+    workers: Vec<(usize, String)>,
+    config: ResolvedConfig,
+}
+
+impl KeepAliveController {
+    fn new() -> Self {
+        Self {
+            children: Vec::new(),
+            session: None,
+            user_session: None,
+            status: None,
+            log_file: None,
+            supervise: false,
+            restarts: 0,
+        }
+    }
+
+    /// A controller whose workers report their status to `board`. Workers in
+    /// another session have no pipe back and never report.
+    fn reporting_to(board: Arc<StatusBoard>) -> Self {
+        let mut controller = Self::new();
+        controller.status = Some(board);
+        controller
+    }
+
+    /// A controller whose workers run in another user's session (`--session`).
+    fn in_session(session_id: u32) -> Self {
+        let mut controller = Self::new();
+        controller.user_session = Some(session_id);
+        controller
+    }
+
+    /// Starts a worker together with its shutdown event. Workers in another
+    /// session can only see events in the global namespace.
+    fn spawn(&self, mut cmd: Command) -> Result<Worker> {
+        let namespace = if self.user_session.is_some() { "Global" } else { "Local" };
+        let id = NEXT_WORKER_ID.fetch_add(1, Ordering::Relaxed);
+        let name = format!("{}\\KeepActive-shutdown-{}-{}", namespace, std::process::id(), id);
+        let shutdown = ShutdownEvent::create(&name);
+        if shutdown.is_some() {
+            cmd.arg("--shutdown-event").arg(&name);
+        }
+        if let Some(path) = &self.log_file {
+            cmd.arg("--log-file").arg(worker_log_path(path, id));
+        }
+        if self.status.is_some() && self.user_session.is_none() {
+            cmd.arg("--report-status").stdout(Stdio::piped());
+        }
+        let process = self.start_process(id, &mut cmd)?;
+        Ok(Worker {
+            id,
+            process,
+            shutdown,
+            command: cmd,
+            started_at: Instant::now(),
+        })
+    }
+
+    /// Runs a worker's command, for the first time or again under
+    /// `--supervise`.
+    fn start_process(&self, id: usize, cmd: &mut Command) -> Result<WorkerProcess> {
+        Ok(match self.user_session {
+            None => {
+                let mut child = cmd.spawn()?;
+                if let (Some(board), Some(stdout)) = (&self.status, child.stdout.take()) {
+                    board.follow(id, stdout);
+                }
+                WorkerProcess::Local(child)
+            }
+            Some(session_id) => WorkerProcess::InSession(spawn_in_session(cmd, session_id)?),
+        })
+    }
+
+    fn start(&mut self, config: ResolvedConfig) -> Result<()> {
+        self.prune_finished();
+        if !self.children.is_empty() {
+            return Ok(());
+        }
+        config.validate()?;
+        self.log_file = config.options.log_file.clone();
+        self.supervise = config.options.supervise;
+
+        let session_config = config.clone();
+        let ResolvedConfig {
+            window_titles,
+            process_names,
+            pids,
+            exe_paths,
+            path_fragments,
+            title_regexes,
+            title_exe_pairs,
+            class_matches,
+            mirrors,
+            hwnd_files,
+            target_intervals,
+            options,
+        } = config;
+
+        let window_titles = normalize_list(window_titles);
+        let mut process_names = normalize_list(process_names);
+        add_primary(&mut process_names, options.primary.as_deref());
+        let path_fragments = normalize_list(path_fragments);
+
+        if window_titles.is_empty() && process_names.is_empty() && path_fragments.is_empty() {
+            return Err(anyhow!("no targets configured"));
+        }
+
+        let exe_path = env::current_exe().context("failed to locate KeepActive executable")?;
+
+        let mut children = Vec::new();
+        let mut workers = Vec::new();
+
+        if options.jiggle_only {
+            // Only the jiggle worker below runs.
+        } else if options.rotate.is_some() || options.all_windows {
+            // Slices must not overlap, and `--all` must see every rule to
+            // activate a window matched twice only once, so one worker owns
+            // every rule.
+            let mut cmd = Self::worker_command(&exe_path, &options);
+            for title in &window_titles {
+                for candidate in title_candidates(title, options.title_alternatives) {
+                    cmd.arg("--window").arg(candidate);
+                }
+            }
+            for name in &process_names {
+                cmd.arg("--exe").arg(name);
+            }
+            for pid in &pids {
+                cmd.arg("--pid").arg(pid.to_string());
+            }
+            for path in &exe_paths {
+                cmd.arg("--exe-path").arg(path);
+            }
+            for fragment in &path_fragments {
+                cmd.arg("--exe-path-contains").arg(fragment);
+            }
+            for pattern in &title_regexes {
+                cmd.arg("--title-regex").arg(pattern.as_str());
+            }
+            for pair in &title_exe_pairs {
+                cmd.arg("--match-title").arg(&pair.title);
+                cmd.arg("--match-exe").arg(&pair.exe);
+            }
+            for rule in &class_matches {
+                cmd.arg("--class").arg(rule.to_string());
+            }
+            for path in &hwnd_files {
+                cmd.arg("--hwnd-file").arg(path);
+            }
+            let role = if options.rotate.is_some() { "rotation" } else { "all-windows" };
+            let child =
+                self.spawn(cmd).with_context(|| format!("failed to launch {} worker", role))?;
+            children.push(child);
+            let rules = window_titles.len()
+                + process_names.len()
+                + pids.len()
+                + exe_paths.len()
+                + path_fragments.len()
+                + title_regexes.len()
+                + title_exe_pairs.len()
+                + class_matches.len()
+                + hwnd_files.len();
+            if options.rotate.is_some() {
+                workers.push(format!("rotation across {} rules", rules));
+            } else {
+                workers.push(format!("all windows across {} rules", rules));
+            }
+        } else {
+            for title in &window_titles {
+                let rule_options = options.for_rule(&target_intervals, title);
+                let mut cmd = Self::worker_command(&exe_path, &rule_options);
+                for candidate in title_candidates(title, options.title_alternatives) {
+                    cmd.arg("--window").arg(candidate);
+                }
+                let child = self
+                    .spawn(cmd)
+                    .with_context(|| format!("failed to launch worker for window '{}'", title))?;
+                children.push(child);
+                workers.push(format!("window: {}", title));
+            }
+
+            for name in &process_names {
+                let rule_options = options.for_rule(&target_intervals, name);
+                let mut cmd = Self::worker_command(&exe_path, &rule_options);
+                Self::push_fallback_titles(&mut cmd, &window_titles, &options);
+                cmd.arg("--exe").arg(name);
+                let child = self
+                    .spawn(cmd)
+                    .with_context(|| format!("failed to launch worker for executable '{}'", name))?;
+                children.push(child);
+                workers.push(format!("exe: {}", name));
+            }
+
+            for pid in &pids {
+                let mut cmd = Self::worker_command(&exe_path, &options);
+                Self::push_fallback_titles(&mut cmd, &window_titles, &options);
+                cmd.arg("--pid").arg(pid.to_string());
+                let child = self
+                    .spawn(cmd)
+                    .with_context(|| format!("failed to launch worker for PID {}", pid))?;
+                children.push(child);
+                workers.push(format!("pid: {}", pid));
+            }
+
+            for path in &exe_paths {
+                let mut cmd = Self::worker_command(&exe_path, &options);
+                Self::push_fallback_titles(&mut cmd, &window_titles, &options);
+                cmd.arg("--exe-path").arg(path);
+                let child = self.spawn(cmd).with_context(|| {
+                    format!("failed to launch worker for executable '{}'", path)
+                })?;
+                children.push(child);
+                workers.push(format!("exe path: {}", path));
+            }
+
+            for fragment in &path_fragments {
+                let mut cmd = Self::worker_command(&exe_path, &options);
+                Self::push_fallback_titles(&mut cmd, &window_titles, &options);
+                cmd.arg("--exe-path-contains").arg(fragment);
+                let child = self.spawn(cmd).with_context(|| {
+                    format!("failed to launch worker for executable path '{}'", fragment)
+                })?;
+                children.push(child);
+                workers.push(format!("exe path contains: {}", fragment));
+            }
+
+            for pattern in &title_regexes {
+                let mut cmd = Self::worker_command(&exe_path, &options);
+                Self::push_fallback_titles(&mut cmd, &window_titles, &options);
+                cmd.arg("--title-regex").arg(pattern.as_str());
+                let child = self.spawn(cmd).with_context(|| {
+                    format!("failed to launch worker for title regex '{}'", pattern)
+                })?;
+                children.push(child);
+                workers.push(format!("title regex: {}", pattern));
+            }
+
+            // Pairs get no fallback titles: they exist to be stricter than either half.
+            for pair in &title_exe_pairs {
+                let mut cmd = Self::worker_command(&exe_path, &options);
+                cmd.arg("--match-title").arg(&pair.title);
+                cmd.arg("--match-exe").arg(&pair.exe);
+                let child = self.spawn(cmd).with_context(|| {
+                    format!(
+                        "failed to launch worker for '{}' in '{}'",
+                        pair.title, pair.exe
+                    )
+                })?;
+                children.push(child);
+                workers.push(format!("title '{}' in exe: {}", pair.title, pair.exe));
+            }
+
+            // Like pairs, class rules are meant to be stricter than titles.
+            for rule in &class_matches {
+                let mut cmd = Self::worker_command(&exe_path, &options);
+                cmd.arg("--class").arg(rule.to_string());
+                let child = self.spawn(cmd).with_context(|| {
+                    format!("failed to launch worker for window class '{}'", rule)
+                })?;
+                children.push(child);
+                workers.push(format!("class: {}", rule));
+            }
+
+            for path in &hwnd_files {
+                let mut cmd = Self::worker_command(&exe_path, &options);
+                cmd.arg("--hwnd-file").arg(path);
+                let child = self.spawn(cmd).with_context(|| {
+                    format!("failed to launch worker for handle file '{}'", path.display())
+                })?;
+                children.push(child);
+                workers.push(format!("hwnd file: {}", path.display()));
+            }
+        }
+
+        for mirror in &mirrors {
+            let mut cmd = Self::worker_command(&exe_path, &options);
+            cmd.arg("--mirror").arg(mirror.to_string());
+            let child = self
+                .spawn(cmd)
+                .with_context(|| format!("failed to launch worker for mirror '{}'", mirror))?;
+            children.push(child);
+            workers.push(format!("mirror: {}", mirror));
+        }
+
+        if options.jiggle {
+            let mut cmd = Self::worker_command(&exe_path, &options);
+            cmd.arg("--jiggle");
+            let child = self.spawn(cmd).context("failed to launch jiggle worker")?;
+            children.push(child);
+            workers.push("pointer jiggle".to_string());
+        }
+
+        let workers = children.iter().map(|child| child.id).zip(workers).collect();
+        self.children = children;
+        self.session = Some(SessionInfo {
+            started_at: Instant::now(),
+            workers,
+            config: session_config,
+        });
+        Ok(())
+    }
+
+    /// Respawns every worker from the running session's config so targets
+    /// are resolved from scratch on the current desktop.
+    fn restart(&mut self) -> Result<()> {
+        let Some(session) = self.session.as_ref() else {
+            return Ok(());
+        };
+        let config = session.config.clone();
+        self.stop()?;
+        self.start(config)
+    }
+
+    fn push_fallback_titles(cmd: &mut Command, window_titles: &[String], options: &WorkerOptions) {
+        for title in window_titles {
+            for candidate in title_candidates(title, options.title_alternatives) {
+                cmd.arg("--window").arg(candidate);
+            }
+        }
+    }
+
+    fn worker_command(exe_path: &Path, options: &WorkerOptions) -> Command {
+        let mut cmd = Command::new(exe_path);
+        cmd.arg("--worker");
+        options.push_args(&mut cmd);
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .creation_flags(CREATE_NO_WINDOW);
+        cmd
+    }
+
+    /// Stops every worker and describes the session that ran, or `None` if
+    /// nothing was started. Workers write a last status line as they shut
+    /// down, so the counts include their final activations.
+    fn finish(&mut self, reason: &str) -> Result<Option<SessionSummary>> {
+        let Some(session) = self.session.take() else {
+            return self.stop().map(|()| None);
+        };
+        let stopped = self.stop();
+        if let Some(board) = &self.status {
+            board.drain(STATUS_DRAIN);
+        }
+        let workers = session
+            .workers
+            .into_iter()
+            .map(|(id, label)| (label, self.status.as_ref().and_then(|board| board.counts(id))))
+            .collect();
+        stopped.map(|()| Some(SessionSummary::new(reason, session.started_at.elapsed(), workers)))
+    }
+
+    /// The summary of a session whose workers have all exited on their own,
+    /// e.g. after `--max-duration`; `None` while any still runs.
+    fn ended(&mut self, reason: &str) -> Option<SessionSummary> {
+        if self.session.is_none() || self.is_running() {
+            return None;
+        }
+        self.finish(reason).ok().flatten()
+    }
+
+    /// Asks every worker to exit and kills those still running after
+    /// `WORKER_SHUTDOWN_GRACE`. All are signalled first, so the grace
+    /// periods run in parallel.
+    fn stop(&mut self) -> Result<()> {
+        self.session = None;
+        for child in &self.children {
+            child.request_shutdown();
+        }
+        let deadline = Instant::now() + WORKER_SHUTDOWN_GRACE;
+        for mut child in self.children.drain(..) {
+            child.stop_by(deadline)?;
+        }
+        Ok(())
+    }
+
+    fn is_running(&mut self) -> bool {
+        self.prune_finished();
+        !self.children.is_empty()
+    }
+
+    /// Drops workers that have exited. Under `--supervise`, one that failed
+    /// (a non-zero exit code, as from a crash) is started again with the same
+    /// command, unless it failed again within `SUPERVISE_MIN_UPTIME`.
+    fn prune_finished(&mut self) {
+        let mut active_children = Vec::new();
+        for mut child in std::mem::take(&mut self.children) {
+            match child.exit_code() {
+                None => active_children.push(child),
+                Some(0) => {}
+                Some(_) if self.supervise && child.started_at.elapsed() >= SUPERVISE_MIN_UPTIME => {
+                    if let Ok(process) = self.start_process(child.id, &mut child.command) {
+                        child.process = process;
+                        child.started_at = Instant::now();
+                        self.restarts += 1;
+                        active_children.push(child);
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        self.children = active_children;
+    }
+
+    /// How many workers `--supervise` restarted since the last call.
+    fn take_restarts(&mut self) -> usize {
+        std::mem::take(&mut self.restarts)
+    }
+}
+
+impl Drop for KeepAliveController {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Activations a worker has sent, and how many of them could not be
+/// delivered (a failed post, or a window that had closed meanwhile).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ActivationCounts {
+    sent: u64,
+    failed: u64,
+}
+
+/// What a session did, printed by the CLI and shown by the GUI when it ends.
+/// `--json` prints it as is.
+#[derive(Debug, serde::Serialize)]
+struct SessionSummary {
+    exit_reason: String,
+    uptime_secs: u64,
+    activations: u64,
+    failures: u64,
+    workers: Vec<WorkerSummary>,
+}
+
+/// One worker's part of a `SessionSummary`. The counts are `None` for
+/// workers that do not report status (`--rotate`, `--mirror`, jiggle and
+/// `--session` workers).
+#[derive(Debug, serde::Serialize)]
+struct WorkerSummary {
+    worker: String,
+    activations: Option<u64>,
+    failures: Option<u64>,
+}
+
+impl SessionSummary {
+    fn new(
+        reason: &str,
+        uptime: Duration,
+        workers: Vec<(String, Option<ActivationCounts>)>,
+    ) -> Self {
+        let counts = workers.iter().filter_map(|(_, counts)| *counts);
+        Self {
+            exit_reason: reason.to_string(),
+            uptime_secs: uptime.as_secs(),
+            activations: counts.clone().map(|counts| counts.sent).sum(),
+            failures: counts.map(|counts| counts.failed).sum(),
+            workers: workers
+                .into_iter()
+                .map(|(worker, counts)| WorkerSummary {
+                    worker,
+                    activations: counts.map(|counts| counts.sent),
+                    failures: counts.map(|counts| counts.failed),
+                })
+                .collect(),
+        }
+    }
+
+    fn uptime(&self) -> String {
+        let uptime = self.uptime_secs;
+        format!("{:02}:{:02}:{:02}", uptime / 3600, uptime / 60 % 60, uptime % 60)
+    }
+
+    fn text(&self) -> String {
+        let mut lines = vec![
+            "Session summary".to_string(),
+            format!("  Exit reason: {}", self.exit_reason),
+            format!("  Uptime: {}", self.uptime()),
+            format!("  Activations: {} sent, {} failed", self.activations, self.failures),
+            format!("  Workers: {}", self.workers.len()),
+        ];
+        for worker in &self.workers {
+            lines.push(match (worker.activations, worker.failures) {
+                (Some(sent), Some(failed)) => {
+                    format!("    {} ({} sent, {} failed)", worker.worker, sent, failed)
+                }
+                _ => format!("    {}", worker.worker),
+            });
+        }
+        lines.join("\n")
+    }
+
+    /// For the GUI status line, e.g. "00:10:00, 120 sent, 3 failed".
+    fn brief(&self) -> String {
+        format!("{}, {} sent, {} failed", self.uptime(), self.activations, self.failures)
+    }
+
+    fn json(&self) -> String {
+        serde_json::to_string(self).expect("a session summary always serializes")
+    }
+}
+
+/// A running worker and the event that asks it to exit. Without the event
+/// (it could not be created) the worker can only be killed.
+struct Worker {
+    id: usize,
+    process: WorkerProcess,
+    shutdown: Option<ShutdownEvent>,
+    /// Kept so `--supervise` can start the worker again as it was.
+    command: Command,
+    started_at: Instant,
+}
+
+impl Worker {
+    fn request_shutdown(&self) {
+        if let Some(event) = &self.shutdown {
+            let _ = unsafe { SetEvent(event.0) };
+        }
+    }
+
+    /// Waits until `deadline` for a signalled worker to exit on its own,
+    /// then kills it.
+    fn stop_by(&mut self, deadline: Instant) -> io::Result<()> {
+        if self.shutdown.is_some() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if self.process.wait_for_exit(remaining) {
+                return Ok(());
+            }
+        }
+        self.process.terminate()
+    }
+
+    fn exit_code(&mut self) -> Option<u32> {
+        self.process.exit_code()
+    }
+}
+
+/// A manual-reset event named after the controller and a counter.
+struct ShutdownEvent(HANDLE);
+
+impl ShutdownEvent {
+    fn create(name: &str) -> Option<Self> {
+        let wide = to_wide(name);
+        unsafe { CreateEventW(None, true, false, PCWSTR(wide.as_ptr())) }.ok().map(Self)
+    }
+}
+
+impl Drop for ShutdownEvent {
+    fn drop(&mut self) {
+        let _ = unsafe { CloseHandle(self.0) };
+    }
+}
+
+static NEXT_WORKER_ID: AtomicUsize = AtomicUsize::new(0);
+const WORKER_SHUTDOWN_GRACE: Duration = Duration::from_millis(1000);
+/// How long a session summary waits for the stopped workers' last status
+/// lines to be read.
+const STATUS_DRAIN: Duration = Duration::from_millis(500);
+/// A worker that fails sooner than this after starting is left stopped, so
+/// one that crashes on start is not restarted over and over.
+const SUPERVISE_MIN_UPTIME: Duration = Duration::from_secs(10);
+
+/// The latest `--report-status` line of every worker, fed by one reader
+/// thread per worker stdout. A reader ends, and drops its worker's entry, when
+/// the worker exits and its end of the pipe closes, so stopping the
+/// controller stops the readers too. Each change wakes the GUI through
+/// `notice`; the CLI has none and only reads the counts.
+struct StatusBoard {
+    workers: Mutex<HashMap<usize, Option<String>>>,
+    /// Kept after a worker exits, for the session summary.
+    counts: Mutex<HashMap<usize, ActivationCounts>>,
+    /// Reader threads still running, and the signal that one has ended.
+    readers: Mutex<usize>,
+    reader_done: Condvar,
+    notice: Option<nwg::NoticeSender>,
+}
+
+impl StatusBoard {
+    fn new(notice: Option<nwg::NoticeSender>) -> Self {
+        Self {
+            workers: Mutex::new(HashMap::new()),
+            counts: Mutex::new(HashMap::new()),
+            readers: Mutex::new(0),
+            reader_done: Condvar::new(),
+            notice,
+        }
+    }
+
+    fn follow(self: &Arc<Self>, id: usize, stdout: ChildStdout) {
+        let board = Arc::clone(self);
+        *self.readers.lock().unwrap_or_else(PoisonError::into_inner) += 1;
+        thread::spawn(move || {
+            for line in io::BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                let (found, counts) = parse_status_line(&line);
+                board.counts.lock().unwrap_or_else(PoisonError::into_inner).insert(id, counts);
+                board.update(id, Some(found));
+            }
+            board.update(id, None);
+            *board.readers.lock().unwrap_or_else(PoisonError::into_inner) -= 1;
+            board.reader_done.notify_all();
+        });
+    }
+
+    /// Waits up to `timeout` for every reader to reach the end of its
+    /// worker's output. A worker's child process can inherit the pipe and
+    /// keep it open, so this does not wait for good.
+    fn drain(&self, timeout: Duration) {
+        let readers = self.readers.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = self.reader_done.wait_timeout_while(readers, timeout, |readers| *readers > 0);
+    }
+
+    /// The counts in worker `id`'s last report, if it sent one.
+    fn counts(&self, id: usize) -> Option<ActivationCounts> {
+        self.counts.lock().unwrap_or_else(PoisonError::into_inner).get(&id).copied()
+    }
+
+    /// `Some(found)` records a status line, `None` forgets the worker.
+    fn update(&self, id: usize, status: Option<Option<String>>) {
+        let mut workers = self.workers.lock().unwrap_or_else(PoisonError::into_inner);
+        let changed = match status {
+            Some(found) => workers.insert(id, found.clone()) != Some(found),
+            None => workers.remove(&id).is_some(),
+        };
+        drop(workers);
+        if changed && let Some(notice) = &self.notice {
+            notice.notice();
+        }
+    }
+
+    /// E.g. "Active: CounterSide (found)", or `None` before any worker has
+    /// reported.
+    fn summary(&self) -> Option<String> {
+        let workers = self.workers.lock().unwrap_or_else(PoisonError::into_inner);
+        if workers.is_empty() {
+            return None;
+        }
+        let mut found: Vec<&str> = workers.values().flatten().map(String::as_str).collect();
+        found.sort_unstable();
+        found.dedup();
+        Some(if found.is_empty() {
+            "Searching\u{2026} (no window)".to_string()
+        } else {
+            format!("Active: {} (found)", found.join(", "))
+        })
+    }
+}
+
+/// A worker's process. `--session` workers are started with
+/// CreateProcessAsUserW, which `Command` cannot do, so only their process
+/// handle is held.
+enum WorkerProcess {
+    Local(Child),
+    InSession(SessionProcess),
+}
+
+impl WorkerProcess {
+    fn handle(&self) -> HANDLE {
+        match self {
+            WorkerProcess::Local(child) => HANDLE(child.as_raw_handle()),
+            WorkerProcess::InSession(process) => process.0,
+        }
+    }
+
+    fn wait_for_exit(&mut self, timeout: Duration) -> bool {
+        let millis = timeout.as_millis().min(u32::MAX as u128) as u32;
+        let exited = unsafe { WaitForSingleObject(self.handle(), millis) } == WAIT_OBJECT_0;
+        if exited && let WorkerProcess::Local(child) = self {
+            let _ = child.wait();
+        }
+        exited
+    }
+
+    fn terminate(&mut self) -> io::Result<()> {
+        match self {
+            WorkerProcess::Local(child) => {
+                if let Err(err) = child.kill()
+                    && err.kind() != io::ErrorKind::InvalidInput
+                {
+                    return Err(err);
+                }
+                let _ = child.wait();
+            }
+            WorkerProcess::InSession(process) => {
+                // Fails with access denied once the process has already exited.
+                if let Err(err) = unsafe { TerminateProcess(process.0, 1) }
+                    && !process.has_exited()
+                {
+                    return Err(err.into());
+                }
+                unsafe { WaitForSingleObject(process.0, WORKER_EXIT_TIMEOUT_MS) };
+            }
+        }
+        Ok(())
+    }
+
+    /// `None` while the worker runs. A failed query counts as still running,
+    /// as before.
+    fn exit_code(&mut self) -> Option<u32> {
+        match self {
+            WorkerProcess::Local(child) => {
+                let status = child.try_wait().ok().flatten()?;
+                Some(status.code().map_or(1, |code| code as u32))
+            }
+            WorkerProcess::InSession(process) => process.exit_code(),
+        }
+    }
+}
+
+struct SessionProcess(HANDLE);
+
+impl SessionProcess {
+    fn has_exited(&self) -> bool {
+        let state = unsafe { WaitForSingleObject(self.0, 0) };
+        state == WAIT_OBJECT_0
+    }
+
+    fn exit_code(&self) -> Option<u32> {
+        if !self.has_exited() {
+            return None;
+        }
+        let mut code = 1;
+        let _ = unsafe { GetExitCodeProcess(self.0, &mut code) };
+        Some(code)
+    }
+}
+
+impl Drop for SessionProcess {
+    fn drop(&mut self) {
+        let _ = unsafe { CloseHandle(self.0) };
+    }
+}
+
+const WORKER_EXIT_TIMEOUT_MS: u32 = 5000;
+
+/// Starts `cmd` on the interactive desktop of `session_id` as the user logged
+/// on there. WTSQueryUserToken needs SeTcbPrivilege, which only LocalSystem
+/// holds by default: an elevated administrator gets ERROR_PRIVILEGE_NOT_HELD,
+/// and a session with nobody logged on gives ERROR_NO_TOKEN.
+fn spawn_in_session(cmd: &Command, session_id: u32) -> Result<SessionProcess> {
+    let mut token = HANDLE::default();
+    unsafe { WTSQueryUserToken(session_id, &mut token) }.with_context(|| {
+        format!(
+            "cannot get the user token of session {} (needs SYSTEM and a logged-on user)",
+            session_id
+        )
+    })?;
+    let mut command_line: Vec<String> =
+        vec![quote_argument(&cmd.get_program().to_string_lossy())];
+    command_line.extend(cmd.get_args().map(|arg| quote_argument(&arg.to_string_lossy())));
+    let mut command_line = to_wide(&command_line.join(" "));
+    let mut desktop = to_wide("winsta0\\default");
+    let startup = STARTUPINFOW {
+        cb: std::mem::size_of::<STARTUPINFOW>() as u32,
+        lpDesktop: PWSTR(desktop.as_mut_ptr()),
+        ..Default::default()
+    };
+    let mut info = PROCESS_INFORMATION::default();
+    let created = unsafe {
+        CreateProcessAsUserW(
+            token,
+            None,
+            PWSTR(command_line.as_mut_ptr()),
+            None,
+            None,
+            false,
+            PROCESS_CREATION_FLAGS(CREATE_NO_WINDOW),
+            None,
+            None,
+            &startup,
+            &mut info,
+        )
+    };
+    unsafe {
+        let _ = CloseHandle(token);
+    }
+    created.with_context(|| format!("CreateProcessAsUserW failed for session {}", session_id))?;
+    unsafe {
+        let _ = CloseHandle(info.hThread);
+    }
+    Ok(SessionProcess(info.hProcess))
+}
+
+/// The KeepActive binary: parses the command line, then runs the GUI, the
+/// CLI or a worker.
+pub fn main() -> Result<()> {
+    let args = Args::parse();
+    check_title_exe_pairs(&args)?;
+    let mut config = AppConfig::from_config_file(&args)?;
+    // Workers get the resolved paths from the controller, never the shortcut.
+    for shortcut in &args.from_shortcut {
+        config.path_fragments.push(resolve_shortcut(shortcut)?);
+    }
+    if args.crash_dumps {
+        install_crash_dump_handler();
+    }
+    if let Some(path) = &args.diagnostics {
+        write_diagnostics(&config, path, args.redact_titles)?;
+        println!("Diagnostics written to {}", path.display());
+        return Ok(());
+    }
+    // Session 0 and invisible window stations have no desktop to act on;
+    // without this check everything starts and then silently does nothing.
+    // A --session controller only starts workers, which run the check again
+    // in the target session.
+    if args.session.is_none() {
+        ensure_interactive_session()?;
+    }
+    if args.check_target {
+        let found = check_target(&config.resolved());
+        std::process::exit(if found { 0 } else { 1 });
+    }
+    if args.list {
+        list_targets(&config.resolved());
+        return Ok(());
+    }
+    // Validate before elevating or hiding the console so the message is seen.
+    config.resolved().validate()?;
+    if args.install_autostart {
+        return install_autostart();
+    }
+    if args.uninstall_autostart {
+        return uninstall_autostart();
+    }
+    // Only one GUI or CLI controller at a time; workers belong to one. The
+    // first check skips a pointless UAC prompt, the second claims the mutex
+    // once elevated (the unelevated process would still hold it otherwise).
+    let controller = !args.worker && !args.worker_debug;
+    if controller && another_instance_running() {
+        switch_to_running_instance();
+        return Ok(());
+    }
+    if !args.no_admin {
+        ensure_admin(!args.cli && !args.worker && !args.worker_debug)?;
+    }
+    let _instance = if controller {
+        let Some(instance) = InstanceMutex::acquire() else {
+            switch_to_running_instance();
+            return Ok(());
+        };
+        Some(instance)
+    } else {
+        None
+    };
+    if (args.worker || !args.cli) && !args.worker_debug {
+        hide_console_window();
+    }
+
+    if args.worker || args.worker_debug {
+        let shutdown_event = args.shutdown_event.as_deref();
+        run_worker(config.resolved(), args.worker_debug, shutdown_event, args.report_status)?;
+    } else if args.cli {
+        run_cli(config)?;
+    } else {
+        run_gui(config)?;
+    }
+    Ok(())
+}
+
+fn check_title_exe_pairs(args: &Args) -> Result<()> {
+    if args.match_title.len() != args.match_exe.len() {
+        return Err(anyhow!(
+            "--match-title and --match-exe must be given the same number of times ({} vs {})",
+            args.match_title.len(),
+            args.match_exe.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Library entry point for embedding the activation loop in another program.
+pub struct KeepActive;
+
+impl KeepActive {
+    /// Keeps the windows `config` matches active on the calling thread until
+    /// `active` is cleared, like a worker process does. Nothing is spawned,
+    /// elevated or shown: the caller owns the thread and the process.
+    pub fn run(config: ResolvedConfig, active: Arc<AtomicBool>) {
+        run_activation(config, active, false, None);
+    }
+}
+
+fn run_worker(
+    config: ResolvedConfig,
+    verbose: bool,
+    shutdown_event: Option<&str>,
+    report_status: bool,
+) -> Result<()> {
+    if verbose {
+        println!("KeepActive - worker debug");
+        println!("Target executables: {}", display_list(&config.process_names));
+        println!("Fallback window titles: {}", display_list(&config.window_titles));
+        println!("----------------------------------------");
+    }
+    let active = Arc::new(AtomicBool::new(true));
+    if let Some(name) = shutdown_event {
+        watch_shutdown_event(name, Arc::clone(&active));
+    }
+    run_activation(config, active, verbose, report_status.then(StatusReporter::default));
+    Ok(())
+}
+
+/// Runs whichever loop `config` calls for until `active` is cleared.
+fn run_activation(
+    config: ResolvedConfig,
+    active: Arc<AtomicBool>,
+    verbose: bool,
+    status: Option<StatusReporter>,
+) {
+    let _priority = PriorityGuard::raise(config.options.priority);
+    let _awake = AwakeGuard::hold(config.options.prevent_sleep);
+    #[cfg(feature = "audio")]
+    if config.options.only_when_playing {
+        // Failure surfaces later as "not playing"; the worker keeps running.
+        let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+    }
+    let log = config.options.log_file.as_deref().and_then(WorkerLog::open);
+    if let Some(log) = &log {
+        log.line(&format!("started, {}", rule_description(&config)));
+    }
+    if let Some(delay) = config.options.start_delay {
+        if verbose {
+            println!("waiting {}s before the first iteration", delay.as_secs());
+        }
+        sleep_while_active(&active, delay);
+    }
+    if config.options.jiggle {
+        jiggle_loop(&active, &config, verbose);
+    } else if !config.mirrors.is_empty() {
+        mirror_loop(active, config, verbose);
+    } else {
+        match config.options.rotate {
+            Some(slice) => rotate_loop(active, config, slice, verbose),
+            None => worker_loop(active, config, verbose, status, log.as_ref()),
+        }
+    }
+    if let Some(log) = &log {
+        log.line("stopped");
+    }
+}
+
+/// Clears `active` once the controller signals the event, so whichever loop
+/// is running returns and the worker's guards are dropped. If the event
+/// cannot be opened, the controller kills the worker after the grace period.
+fn watch_shutdown_event(name: &str, active: Arc<AtomicBool>) {
+    let wide = to_wide(name);
+    let opened = unsafe { OpenEventW(SYNCHRONIZATION_SYNCHRONIZE, false, PCWSTR(wide.as_ptr())) };
+    let Ok(event) = opened else {
+        return;
+    };
+    // HANDLE is not Send; the thread owns the raw value from here on.
+    let raw = event.0 as usize;
+    thread::spawn(move || {
+        let event = HANDLE(raw as *mut c_void);
+        if unsafe { WaitForSingleObject(event, INFINITE) } == WAIT_OBJECT_0 {
+            active.store(false, Ordering::SeqCst);
+        }
+        let _ = unsafe { CloseHandle(event) };
+    });
+}
+
+/// Raises the worker's process and thread priority, dropping back to normal
+/// when the worker loop returns.
+struct PriorityGuard {
+    raised: bool,
+}
+
+impl PriorityGuard {
+    fn raise(priority: Priority) -> Self {
+        let (class, thread) = match priority {
+            Priority::Normal => return Self { raised: false },
+            Priority::High => (HIGH_PRIORITY_CLASS, THREAD_PRIORITY_HIGHEST),
+            // Without SeIncreaseBasePriorityPrivilege Windows quietly caps this at HIGH.
+            Priority::Realtime => (REALTIME_PRIORITY_CLASS, THREAD_PRIORITY_TIME_CRITICAL),
+        };
+        unsafe {
+            let _ = SetPriorityClass(GetCurrentProcess(), class);
+            let _ = SetThreadPriority(GetCurrentThread(), thread);
+        }
+        Self { raised: true }
+    }
+}
+
+impl Drop for PriorityGuard {
+    fn drop(&mut self) {
+        if self.raised {
+            unsafe {
+                let _ = SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_NORMAL);
+                let _ = SetPriorityClass(GetCurrentProcess(), NORMAL_PRIORITY_CLASS);
+            }
+        }
+    }
+}
+
+/// `--prevent-sleep`: asks Windows to keep the system and display awake for
+/// as long as the worker loop runs. The request belongs to the calling
+/// thread, so a worker that is killed outright releases it as its thread
+/// ends; the Drop only matters for the orderly exits.
+struct AwakeGuard {
+    held: bool,
+}
+
+impl AwakeGuard {
+    fn hold(enabled: bool) -> Self {
+        if !enabled {
+            return Self { held: false };
+        }
+        let flags = ES_CONTINUOUS | ES_SYSTEM_REQUIRED | ES_DISPLAY_REQUIRED;
+        // SetThreadExecutionState returns zero when it rejects the request.
+        let previous = unsafe { SetThreadExecutionState(flags) };
+        Self { held: previous.0 != 0 }
+    }
+}
+
+impl Drop for AwakeGuard {
+    fn drop(&mut self) {
+        if self.held {
+            unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+        }
+    }
+}
+
+fn write_diagnostics(config: &AppConfig, path: &Path, redact_titles: bool) -> Result<()> {
+    let title = |value: &str| {
+        if redact_titles {
+            redact(value)
+        } else {
+            quoted_title(value)
+        }
+    };
+
+    let mut report = String::new();
+    let mut line = |text: String| {
+        report.push_str(&text);
+        report.push_str("\r\n");
+    };
+
+    line("KeepActive diagnostics".to_string());
+    line(format!("Version: {}", env!("CARGO_PKG_VERSION")));
+    line(format!("OS: {}", os_version()));
+    line(format!(
+        "Elevated: {}",
+        match is_elevated() {
+            Ok(true) => "yes".to_string(),
+            Ok(false) => "no".to_string(),
+            Err(err) => format!("unknown ({})", err),
+        }
+    ));
+    line(format!("Workstation locked: {}", is_workstation_locked()));
+    line(format!(
+        "Interactive session: {}",
+        match ensure_interactive_session() {
+            Ok(()) => "yes".to_string(),
+            Err(err) => format!("no ({})", err),
+        }
+    ));
+    line(String::new());
+
+    line("[Configuration]".to_string());
+    for window_title in &config.window_titles {
+        line(format!("window: {}", title(window_title)));
+    }
+    for name in &config.process_names {
+        line(format!("exe: {}", name));
+    }
+    for pid in &config.pids {
+        line(format!("pid: {}", pid));
+    }
+    for path in &config.exe_paths {
+        line(format!("exe path: {}", path));
+    }
+    for fragment in &config.path_fragments {
+        line(format!("exe path contains: {}", fragment));
+    }
+    for pattern in &config.title_regexes {
+        line(format!("title regex: {}", title(pattern.as_str())));
+    }
+    for pair in &config.title_exe_pairs {
+        line(format!("title {} in exe: {}", title(&pair.title), pair.exe));
+    }
+    for rule in &config.class_matches {
+        match &rule.title {
+            Some(text) => line(format!("class: {} with title {}", rule.class, title(text))),
+            None => line(format!("class: {}", rule.class)),
+        }
+    }
+    for mirror in &config.mirrors {
+        line(format!("mirror: {}", mirror));
+    }
+    for path in &config.hwnd_files {
+        line(format!("hwnd file: {}", path.display()));
+    }
+    line(format!("{:?}", config.options));
+    line(String::new());
+
+    line("[Validation]".to_string());
+    match config.resolved().validate() {
+        Ok(()) => line("ok".to_string()),
+        Err(err) => line(err.to_string()),
+    }
+    line(String::new());
+
+    line("[Target resolution]".to_string());
+    for entry in format_preview(&preview_targets(&config.resolved()), &title) {
+        line(entry);
+    }
+    line(String::new());
+
+    line("[Windows]".to_string());
+    let names = process_names_by_pid();
+    for info in enumerate_windows().iter().filter(|info| info.visible) {
+        line(format!(
+            "hwnd {:?} pid {} exe {} class {} title {}",
+            info.hwnd.0,
+            info.pid,
+            names.get(&info.pid).map(String::as_str).unwrap_or("?"),
+            info.class,
+            title(&info.title)
+        ));
+    }
+
+    fs::write(path, report)
+        .with_context(|| format!("failed to write diagnostics to {}", path.display()))
+}
+
+/// Resolves the targets the way a worker would and prints a single line about
+/// the outcome. Runs unelevated, so elevated targets may be missed.
+fn check_target(config: &ResolvedConfig) -> bool {
+    match resolve_targets(config).first() {
+        Some(&hwnd) => {
+            let mut pid = 0;
+            unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+            println!(
+                "found: hwnd {:?} pid {} {}",
+                hwnd.0,
+                pid,
+                quoted_title(&window_title(hwnd))
+            );
+            true
+        }
+        None => {
+            println!("not found");
+            false
+        }
+    }
+}
+
+/// `--list`: every window any rule matches, as `--all` would target them.
+fn list_targets(config: &ResolvedConfig) {
+    let windows = find_all_target_windows(config);
+    if windows.is_empty() {
+        println!("no matching windows");
+    }
+    for hwnd in windows {
+        println!(
+            "hwnd {:?} pid {} class {} {}",
+            hwnd.0,
+            window_pid(hwnd).unwrap_or(0),
+            window_class(hwnd),
+            quoted_title(&window_title(hwnd))
+        );
+    }
+}
+
+/// What one configured rule resolves to right now.
+struct RulePreview {
+    kind: &'static str,
+    value: String,
+    resolved: Option<(HWND, u32)>,
+    note: &'static str,
+}
+
+fn preview_targets(config: &ResolvedConfig) -> Vec<RulePreview> {
+    let mut previews = Vec::new();
+    for path in &config.hwnd_files {
+        let resolved = read_hwnd_file(path, &config.options).map(|hwnd| {
+            let mut pid = 0;
+            unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+            (hwnd, pid)
+        });
+        previews.push(RulePreview {
+            kind: "hwnd file",
+            value: path.display().to_string(),
+            resolved,
+            note: "no valid window handle in the file",
+        });
+    }
+    let scan = TargetScan::default();
+    for pair in &config.title_exe_pairs {
+        let pids = scan.pids_named(&pair.exe);
+        let resolved = scan.windows().find(&pids, &config.options, Some(&pair.title));
+        previews.push(RulePreview {
+            kind: "exe+title",
+            value: format!("{} / {}", pair.exe, pair.title),
+            resolved,
+            note: if pids.is_empty() {
+                "process not running"
+            } else {
+                "no window of the process has this title"
+            },
+        });
+    }
+    for rule in &config.class_matches {
+        let resolved = find_window_by_class(&scan, rule, &config.options).map(|hwnd| {
+            let mut pid = 0;
+            unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+            (hwnd, pid)
+        });
+        previews.push(RulePreview {
+            kind: "class",
+            value: rule.to_string(),
+            resolved,
+            note: if rule.title.is_some() {
+                "no visible window of this class has this title"
+            } else {
+                "no visible window of this class"
+            },
+        });
+    }
+    for name in &config.process_names {
+        let pids = scan.pids_named(name);
+        let resolved = select_process_windows(&scan, pids.clone(), &config.options)
+            .first()
+            .copied();
+        let note = if pids.is_empty() {
+            "process not running"
+        } else {
+            "process has no matching window"
+        };
+        previews.push(RulePreview {
+            kind: "exe",
+            value: name.clone(),
+            resolved,
+            note,
+        });
+    }
+    for &pid in &config.pids {
+        let running = scan.processes().iter().any(|process| process.pid == pid);
+        previews.push(RulePreview {
+            kind: "pid",
+            value: pid.to_string(),
+            resolved: find_process_window(&scan, pid, &config.options).map(|hwnd| (hwnd, pid)),
+            note: if running {
+                "process has no matching window"
+            } else {
+                "process not running"
+            },
+        });
+    }
+    for path in &config.exe_paths {
+        let pids = scan.pids_by_path(path);
+        let resolved = select_process_windows(&scan, pids.clone(), &config.options)
+            .first()
+            .copied();
+        previews.push(RulePreview {
+            kind: "exe full path",
+            value: path.clone(),
+            resolved,
+            note: if pids.is_empty() {
+                "no process started from this path (or none we may open)"
+            } else {
+                "process has no matching window"
+            },
+        });
+    }
+    for fragment in &config.path_fragments {
+        let pids = scan.pids_by_path_fragment(fragment);
+        let resolved = select_process_windows(&scan, pids.clone(), &config.options)
+            .first()
+            .copied();
+        previews.push(RulePreview {
+            kind: "exe path",
+            value: fragment.clone(),
+            resolved,
+            note: if pids.is_empty() {
+                "no process with a matching path"
+            } else {
+                "process has no matching window"
+            },
+        });
+    }
+    for pattern in &config.title_regexes {
+        let resolved = find_window_by_regex(&scan, pattern, &config.options).map(|hwnd| {
+            let mut pid = 0;
+            unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+            (hwnd, pid)
+        });
+        previews.push(RulePreview {
+            kind: "title regex",
+            value: pattern.to_string(),
+            resolved,
+            note: "no window title matches",
+        });
+    }
+    for entry in &config.window_titles {
+        for candidate in title_candidates(entry, config.options.title_alternatives) {
+            let resolved = find_window_by_title(&scan, &candidate, &config.options).map(|hwnd| {
+                let mut pid = 0;
+                unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+                (hwnd, pid)
+            });
+            previews.push(RulePreview {
+                kind: "window",
+                value: candidate,
+                resolved,
+                note: "no window with this title",
+            });
+        }
+    }
+    previews
+}
+
+/// Renders previews one rule per line, flagging rules that resolve to nothing
+/// or to a window an earlier rule already claimed.
+fn format_preview(previews: &[RulePreview], title: &dyn Fn(&str) -> String) -> Vec<String> {
+    let rule = |preview: &RulePreview| match preview.kind {
+        "window" => format!("window {}", title(&preview.value)),
+        kind => format!("{} {}", kind, preview.value),
+    };
+    previews
+        .iter()
+        .enumerate()
+        .map(|(index, preview)| match preview.resolved {
+            Some((hwnd, pid)) => {
+                let mut text = format!(
+                    "  {} -> hwnd {:?} pid {} {}",
+                    rule(preview),
+                    hwnd.0,
+                    pid,
+                    title(&window_title(hwnd))
+                );
+                if let Some(earlier) = previews[..index]
+                    .iter()
+                    .find(|other| matches!(other.resolved, Some((other_hwnd, _)) if other_hwnd == hwnd))
+                {
+                    text.push_str(&format!(" [same window as {}]", rule(earlier)));
+                }
+                text
+            }
+            None => format!("! {} -> nothing ({})", rule(preview), preview.note),
+        })
+        .collect()
+}
+
+fn quoted_title(value: &str) -> String {
+    format!("\"{}\"", value)
+}
+
+fn redact(value: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    format!("<redacted {:016x}>", hasher.finish())
+}
+
+fn os_version() -> String {
+    let mut info = OSVERSIONINFOW {
+        dwOSVersionInfoSize: std::mem::size_of::<OSVERSIONINFOW>() as u32,
+        ..Default::default()
+    };
+    if unsafe { RtlGetVersion(&mut info) }.is_ok() {
+        format!(
+            "Windows {}.{}.{}",
+            info.dwMajorVersion, info.dwMinorVersion, info.dwBuildNumber
+        )
+    } else {
+        "unknown".to_string()
+    }
+}
+
+/// `println!` for the CLI's banner, prompts and messages, which go to stderr
+/// under `--json` so stdout carries nothing but the summary.
+macro_rules! say {
+    ($json:expr) => {
+        if $json { eprintln!() } else { println!() }
+    };
+    ($json:expr, $($arg:tt)*) => {
+        if $json { eprintln!($($arg)*) } else { println!($($arg)*) }
+    };
+}
+
+/// Shows a prompt without a line break, on the stream `say!` uses.
+fn ask(json: bool, prompt: &str) {
+    if json {
+        eprint!("{}", prompt);
+        io::stderr().flush().ok();
+    } else {
+        print!("{}", prompt);
+        io::stdout().flush().ok();
+    }
+}
+
+fn run_cli(mut config: AppConfig) -> Result<()> {
+    let json = config.json;
+    say!(json, "KeepActive - Rust CLI");
+    say!(json, "Target executables: {}", display_list(&config.process_names));
+    say!(json, "Fallback window titles: {}", display_list(&config.window_titles));
+    if config.options.priority == Priority::Realtime {
+        say!(
+            json,
+            "Warning: workers run at realtime priority and may make the system unresponsive."
+        );
+    }
+    say!(json, "----------------------------------------");
+    say!(json, "Commands: 1 = start, 0 = stop, l = pick a window to add, q = quit");
+
+    // Workers report their counts for the session summary.
+    let mut controller = match config.session {
+        Some(session_id) => KeepAliveController::in_session(session_id),
+        None => KeepAliveController::reporting_to(Arc::new(StatusBoard::new(None))),
+    };
+    // The loop wakes up even while nobody types, to report a session whose
+    // workers have all exited and so --supervise can restart workers as they
+    // are checked.
+    let input = CliInput::spawn(config.run_for, Some(Duration::from_secs(1)));
+
+    let mut prompt = true;
+    loop {
+        if prompt {
+            ask(json, "> ");
+        }
+        prompt = true;
+
+        let line = match input.next() {
+            CliLine::Line(line) => line,
+            CliLine::Eof => continue,
+            CliLine::Idle => {
+                let running = controller.is_running();
+                prompt = running && controller.take_restarts() > 0;
+                if prompt {
+                    say!(json);
+                    say!(json, "Restarted a crashed worker.");
+                }
+                if let Some(summary) = controller.ended("all workers exited") {
+                    say!(json);
+                    say!(json, "All workers exited.");
+                    print_summary(Some(summary), &config);
+                    prompt = true;
+                }
+                continue;
+            }
+            CliLine::TimedOut => {
+                let summary = controller.finish("run time limit reached").ok().flatten();
+                say!(json);
+                say!(json, "Run time limit reached. Exiting.");
+                print_summary(summary, &config);
+                break;
+            }
+        };
+        let trimmed = line.trim();
+        match trimmed {
+            "1" => {
+                if controller.is_running() {
+                    say!(json, "Already running.");
+                    continue;
+                }
+                // A session that ended on its own is reported before the new
+                // one replaces it.
+                if let Some(summary) = controller.ended("all workers exited") {
+                    print_summary(Some(summary), &config);
+                }
+                let resolved = config.resolved();
+                if let Err(err) = resolved.validate() {
+                    say!(json, "{}", err);
+                    continue;
+                }
+                if config.preview {
+                    say!(json, "Resolved targets:");
+                    for line in format_preview(&preview_targets(&resolved), &quoted_title) {
+                        say!(json, "{}", line);
+                    }
+                    ask(json, "Start activation? [y/N] ");
+                    let confirmed = match input.read() {
+                        CliLine::Line(answer) => answer.trim().eq_ignore_ascii_case("y"),
+                        CliLine::Eof | CliLine::TimedOut | CliLine::Idle => false,
+                    };
+                    if !confirmed {
+                        say!(json, "Cancelled.");
+                        continue;
+                    }
+                }
+                controller.start(resolved)?;
+                say!(json, "Activation loop started.");
+            }
+            "0" => {
+                if controller.is_running() {
+                    let summary = controller.finish("stopped by user")?;
+                    say!(json, "Activation loop stopped.");
+                    print_summary(summary, &config);
+                } else if let Some(summary) = controller.ended("all workers exited") {
+                    say!(json, "Not running, all workers exited.");
+                    print_summary(Some(summary), &config);
+                } else {
+                    say!(json, "Not running.");
+                }
+            }
+            "q" | "Q" => {
+                let summary = controller.finish("quit").ok().flatten();
+                say!(json, "Exiting.");
+                print_summary(summary, &config);
+                break;
+            }
+            "l" | "L" => pick_window_cli(&mut config, &input),
+            _ => say!(json, "Unknown command: {}", trimmed),
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the summary of a session that just ended, unless `--quiet`.
+fn print_summary(summary: Option<SessionSummary>, config: &AppConfig) {
+    match summary {
+        Some(_) if config.quiet => {}
+        Some(summary) if config.json => println!("{}", summary.json()),
+        Some(summary) => println!("{}", summary.text()),
+        None => {}
+    }
+}
+
+enum CliLine {
+    Line(String),
+    Eof,
+    TimedOut,
+    /// Nothing arrived within the poll interval.
+    Idle,
+}
+
+/// Reads stdin on a helper thread so the command loop can give up waiting
+/// once the `--run-for` deadline passes, or look up every `poll`.
+struct CliInput {
+    lines: Receiver<String>,
+    deadline: Option<Instant>,
+    poll: Option<Duration>,
+}
+
+impl CliInput {
+    fn spawn(run_for: Option<Duration>, poll: Option<Duration>) -> Self {
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            let stdin = io::stdin();
+            let mut buffer = String::new();
+            loop {
+                buffer.clear();
+                match stdin.read_line(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        if sender.send(buffer.clone()).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        Self {
+            lines,
+            deadline: run_for.map(|duration| Instant::now() + duration),
+            poll,
+        }
+    }
+
+    /// The next line, waiting through any number of polls.
+    fn read(&self) -> CliLine {
+        loop {
+            match self.next() {
+                CliLine::Idle => continue,
+                line => return line,
+            }
+        }
+    }
+
+    fn next(&self) -> CliLine {
+        let remaining = self
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        let wait = match (remaining, self.poll) {
+            (Some(remaining), Some(poll)) => Some(remaining.min(poll)),
+            (remaining, poll) => remaining.or(poll),
+        };
+        let received = match wait {
+            Some(wait) => self.lines.recv_timeout(wait),
+            None => self.lines.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let timed_out = || self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        match received {
+            Ok(line) => CliLine::Line(line),
+            Err(RecvTimeoutError::Timeout) if timed_out() => CliLine::TimedOut,
+            Err(RecvTimeoutError::Timeout) => CliLine::Idle,
+            Err(RecvTimeoutError::Disconnected) => match wait {
+                // stdin is gone; keep the session alive until the deadline.
+                Some(wait) => {
+                    thread::sleep(wait);
+                    if timed_out() { CliLine::TimedOut } else { CliLine::Idle }
+                }
+                None => {
+                    thread::sleep(Duration::from_millis(REFRESH_INTERVAL_MS));
+                    CliLine::Eof
+                }
+            },
+        }
+    }
+}
+
+/// Lists visible windows by number and adds the chosen one as a target: a
+/// plain number adds its title, `e<number>` adds its executable.
+fn pick_window_cli(config: &mut AppConfig, input: &CliInput) {
+    let json = config.json;
+    let windows = pickable_windows();
+    if windows.is_empty() {
+        say!(json, "No visible windows found.");
+        return;
+    }
+    for (index, (info, exe)) in windows.iter().enumerate() {
+        say!(json, "{:>3}. {} [{} pid {}]", index + 1, info.title, exe, info.pid);
+    }
+    ask(json, "Add which? (N = title, eN = executable, blank = cancel) ");
+    let CliLine::Line(line) = input.read() else {
+        return;
+    };
+
+    let choice = line.trim();
+    if choice.is_empty() {
+        return;
+    }
+    let (by_exe, number) = match choice.strip_prefix(['e', 'E']) {
+        Some(rest) => (true, rest),
+        None => (false, choice),
+    };
+    let Some((info, exe)) = number
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| windows.get(i))
+    else {
+        say!(json, "Invalid selection: {}", choice);
+        return;
+    };
+
+    let (list, value) = if by_exe {
+        (&mut config.process_names, exe.clone())
+    } else {
+        (&mut config.window_titles, info.title.clone())
+    };
+    list.push(value.clone());
+    *list = normalize_list(std::mem::take(list));
+    say!(json, "Added {} target: {}", if by_exe { "executable" } else { "window" }, value);
+}
+
+/// Loads `--icon` if given, falling back to the icon embedded in the
+/// executable. The second value explains a fallback for the status line.
+fn load_app_icon(path: Option<&Path>) -> (Option<nwg::Icon>, Option<String>) {
+    let mut warning = None;
+    if let Some(path) = path {
+        let mut icon = nwg::Icon::default();
+        let loaded = nwg::Icon::builder()
+            .source_file(Some(&path.to_string_lossy()))
+            .strict(true)
+            .build(&mut icon);
+        match loaded {
+            Ok(()) => return (Some(icon), None),
+            Err(err) => {
+                eprintln!("warning: could not load icon {}: {}", path.display(), err);
+                warning = Some(format!("icon {} not loaded, using default", path.display()));
+            }
+        }
+    }
+    let embedded = nwg::EmbedResource::load(None).ok().and_then(|embed| {
+        let mut icon = nwg::Icon::default();
+        nwg::Icon::builder()
+            .source_embed(Some(&embed))
+            .source_embed_id(1)
+            .build(&mut icon)
+            .ok()
+            .map(|()| icon)
+    });
+    (embedded, warning)
+}
+
+fn run_gui(config: AppConfig) -> Result<()> {
+    nwg::init().context("failed to initialise GUI runtime")?;
+    let _ = nwg::Font::set_global_family("Segoe UI");
+
+    let (icon, icon_warning) = load_app_icon(config.icon.as_deref());
+    let mut window = nwg::Window::default();
+    nwg::Window::builder()
+        .flags(nwg::WindowFlags::WINDOW | nwg::WindowFlags::VISIBLE)
+        .size((420, 520))
+        .title("KeepActive")
+        .icon(icon.as_ref())
+        .build(&mut window)
+        .context("failed to build main window")?;
+
+    // Lists from the command line or a config file win over the saved ones.
+    let launched_with_defaults =
+        config.window_titles == [DEFAULT_WINDOW_TITLE] && config.process_names.is_empty();
+    let lists = launched_with_defaults.then(GuiLists::load).flatten().unwrap_or_else(|| {
+        GuiLists {
+            window_titles: config.window_titles.clone(),
+            process_names: config.process_names.clone(),
+        }
+    });
+
+    let mut _window_label = nwg::Label::default();
+    nwg::Label::builder()
+        .text("Window Titles")
+        .position((20, 20))
+        .size((180, 24))
+        .parent(&window)
+        .build(&mut _window_label)
+        .context("failed to build window label")?;
+
+    let mut window_list: nwg::ListBox<String> = Default::default();
+    nwg::ListBox::builder()
+        .collection(lists.window_titles)
+        .position((20, 48))
+        .size((360, 110))
+        .parent(&window)
+        .build(&mut window_list)
+        .context("failed to build window list box")?;
+    let window_list = Rc::new(window_list);
+
+    let mut window_remove_btn = nwg::Button::default();
+    nwg::Button::builder()
+        .text("Remove")
+        .position((320, 166))
+        .size((60, 28))
+        .parent(&window)
+        .build(&mut window_remove_btn)
+        .context("failed to build window remove button")?;
+    let window_remove_btn = Rc::new(window_remove_btn);
+
+    let mut pick_btn = nwg::Button::default();
+    nwg::Button::builder()
+        .text("Pick Window...")
+        .position((20, 166))
+        .size((120, 28))
+        .parent(&window)
+        .build(&mut pick_btn)
+        .context("failed to build pick window button")?;
+    let pick_btn = Rc::new(pick_btn);
+
+    let mut click_btn = nwg::Button::default();
+    nwg::Button::builder()
+        .text("Click a Window")
+        .position((150, 166))
+        .size((120, 28))
+        .parent(&window)
+        .build(&mut click_btn)
+        .context("failed to build click a window button")?;
+    let click_btn = Rc::new(click_btn);
+
+    let mut _exe_label = nwg::Label::default();
+    nwg::Label::builder()
+        .text("Executable Names (optional)")
+        .position((20, 204))
+        .size((200, 24))
+        .parent(&window)
+        .build(&mut _exe_label)
+        .context("failed to build process label")?;
+
+    let mut exe_list: nwg::ListBox<String> = Default::default();
+    nwg::ListBox::builder()
+        .collection(lists.process_names)
+        .position((20, 200))
+        .size((360, 110))
+        .parent(&window)
+        .build(&mut exe_list)
+        .context("failed to build process list box")?;
+    let exe_list = Rc::new(exe_list);
+
+    let mut exe_remove_btn = nwg::Button::default();
+    nwg::Button::builder()
+        .text("Remove")
+        .position((320, 318))
+        .size((60, 28))
+        .parent(&window)
+        .build(&mut exe_remove_btn)
+        .context("failed to build process remove button")?;
+    let exe_remove_btn = Rc::new(exe_remove_btn);
+
+    let mut _target_label = nwg::Label::default();
+    nwg::Label::builder()
+        .text("Add target (.exe -> executable list)")
+        .position((20, 332))
+        .size((280, 24))
+        .parent(&window)
+        .build(&mut _target_label)
+        .context("failed to build target label")?;
+
+    let mut target_entry = nwg::TextInput::default();
+    nwg::TextInput::builder()
+        .text("")
+        .position((20, 360))
+        .size((220, 28))
+        .parent(&window)
+        .build(&mut target_entry)
+        .context("failed to build target entry input")?;
+    let target_entry = Rc::new(target_entry);
+
+    let mut add_btn = nwg::Button::default();
+    nwg::Button::builder()
+        .text("Add Target")
+        .position((250, 360))
+        .size((130, 28))
+        .parent(&window)
+        .build(&mut add_btn)
+        .context("failed to build add target button")?;
+    let add_btn = Rc::new(add_btn);
+
+    let mut status_label = nwg::Label::default();
+    let initial_status = match &icon_warning {
+        Some(warning) => format!("Status: Not running ({})", warning),
+        None => "Status: Not running".to_string(),
+    };
+    nwg::Label::builder()
+        .text(&initial_status)
+        .position((20, 404))
+        .size((360, 24))
+        .parent(&window)
+        .build(&mut status_label)
+        .context("failed to build status label")?;
+    let status_label = Rc::new(status_label);
+
+    let mut start_btn = nwg::Button::default();
+    nwg::Button::builder()
+        .text("Start")
+        .position((20, 440))
+        .size((160, 32))
+        .parent(&window)
+        .build(&mut start_btn)
+        .context("failed to build start button")?;
+    let start_btn = Rc::new(start_btn);
+
+    let mut stop_btn = nwg::Button::default();
+    nwg::Button::builder()
+        .text("Stop")
+        .enabled(false)
+        .position((220, 440))
+        .size((160, 32))
+        .parent(&window)
+        .build(&mut stop_btn)
+        .context("failed to build stop button")?;
+    let stop_btn = Rc::new(stop_btn);
+
+    let mut run_timer = nwg::AnimationTimer::default();
+    nwg::AnimationTimer::builder()
+        .parent(&window)
+        .interval(config.run_for.unwrap_or(Duration::from_secs(1)))
+        .max_tick(Some(1))
+        .active(config.run_for.is_some())
+        .build(&mut run_timer)
+        .context("failed to build run timer")?;
+
+    // Notices workers that stopped on their own, e.g. after --max-duration.
+    let mut worker_timer = nwg::AnimationTimer::default();
+    nwg::AnimationTimer::builder()
+        .parent(&window)
+        .interval(Duration::from_secs(1))
+        .active(true)
+        .build(&mut worker_timer)
+        .context("failed to build worker timer")?;
+
+    let mut status_notice = nwg::Notice::default();
+    nwg::Notice::builder()
+        .parent(&window)
+        .build(&mut status_notice)
+        .context("failed to build status notice")?;
+    let status_board = Arc::new(StatusBoard::new(Some(status_notice.sender())));
+
+    let picker = WindowPicker::build(&window, icon.as_ref()).context("failed to build picker")?;
+    let picker = Rc::new(picker);
+
+    // The tray needs an icon; without one, closing the window exits as before.
+    let tray = match &icon {
+        Some(icon) => Some(TrayIcon::build(&window, icon).context("failed to build tray icon")?),
+        None => None,
+    };
+
+    struct GuiState {
+        window_list: Rc<nwg::ListBox<String>>,
+        window_remove_btn: Rc<nwg::Button>,
+        pick_btn: Rc<nwg::Button>,
+        click_btn: Rc<nwg::Button>,
+        exe_list: Rc<nwg::ListBox<String>>,
+        exe_remove_btn: Rc<nwg::Button>,
+        target_entry: Rc<nwg::TextInput>,
+        add_btn: Rc<nwg::Button>,
+        status_label: Rc<nwg::Label>,
+        start_btn: Rc<nwg::Button>,
+        stop_btn: Rc<nwg::Button>,
+        tray: Option<TrayIcon>,
+        /// The running click capture and the status text to put back after it.
+        click_capture: RefCell<Option<(ClickCapture, String)>>,
+    }
+
+    impl GuiState {
+        fn save_lists(&self) {
+            GuiLists {
+                window_titles: self.window_list.collection().to_vec(),
+                process_names: self.exe_list.collection().to_vec(),
+            }
+            .save();
+        }
+
+        /// Ends a click capture, adding the window under `point` if there is
+        /// one; `None` means it was cancelled.
+        fn finish_click_capture(&self, point: Option<POINT>) {
+            let Some((capture, status)) = self.click_capture.take() else {
+                return;
+            };
+            drop(capture);
+            self.status_label.set_text(&status);
+            let Some(point) = point else {
+                return;
+            };
+            let hwnd = unsafe { GetAncestor(WindowFromPoint(point), GA_ROOT) };
+            let Some(pid) = window_pid(hwnd).filter(|&pid| pid != unsafe { GetCurrentProcessId() })
+            else {
+                return;
+            };
+            let title = window_title(hwnd);
+            if !title.is_empty() {
+                add_to_list(&self.window_list, title);
+            } else if let Some(exe) = process_names_by_pid().remove(&pid) {
+                add_to_list(&self.exe_list, exe);
+            }
+        }
+    }
+
+    enum GuiCommand {
+        Start(Box<ResolvedConfig>),
+        Stop,
+        RunLimitReached,
+        CheckWorkers,
+        Reattach,
+        Close,
+    }
+
+    /// Controller access for every event handler goes through here. Commands
+    /// are queued and drained one at a time, each under a single short borrow
+    /// of the controller. An event that arrives while a drain is in progress
+    /// (an error dialog pumps messages, for instance) only enqueues, and the
+    /// running drain picks it up, so the controller is never borrowed twice.
+    struct GuiCommands {
+        controller: RefCell<KeepAliveController>,
+        state: Rc<GuiState>,
+        pending: RefCell<VecDeque<GuiCommand>>,
+        draining: Cell<bool>,
+    }
+
+    impl GuiCommands {
+        fn submit(&self, command: GuiCommand) {
+            self.pending.borrow_mut().push_back(command);
+            if self.draining.replace(true) {
+                return;
+            }
+            loop {
+                let next = self.pending.borrow_mut().pop_front();
+                let Some(command) = next else {
+                    break;
+                };
+                let alert = self.execute(command);
+                if let Some(message) = alert {
+                    nwg::simple_message("KeepActive error", &message);
+                }
+            }
+            self.draining.set(false);
+        }
+
+        fn execute(&self, command: GuiCommand) -> Option<String> {
+            let state = &self.state;
+            let (result, was_running, running) = {
+                let mut controller = self.controller.borrow_mut();
+                // The buttons show what the GUI last knew, so a worker that
+                // has exited since still counts as running here.
+                let was_running = state.stop_btn.enabled();
+                // A stopped session's summary goes into the status line.
+                let stopped = |status: &str, summary: Option<SessionSummary>| match summary {
+                    Some(summary) => format!("{} ({})", status, summary.brief()),
+                    None => status.to_string(),
+                };
+                let result = match command {
+                    GuiCommand::Start(config) => {
+                        controller.start(*config).map(|()| "Running".to_string())
+                    }
+                    GuiCommand::Stop => controller
+                        .finish("stopped by user")
+                        .map(|summary| stopped("Not running", summary)),
+                    GuiCommand::RunLimitReached => {
+                        let summary = controller.finish("run time limit reached").ok().flatten();
+                        Ok(stopped("Stopped, run time limit reached", summary))
+                    }
+                    GuiCommand::CheckWorkers => {
+                        let running = controller.is_running();
+                        let restarted = controller.take_restarts() > 0;
+                        if !was_running {
+                            return None;
+                        }
+                        match (running, restarted) {
+                            (false, _) => {
+                                let summary = controller.ended("all workers exited");
+                                Ok(stopped("Not running, all workers finished", summary))
+                            }
+                            (true, true) => Ok("Running (restarted a crashed worker)".to_string()),
+                            (true, false) => return None,
+                        }
+                    }
+                    GuiCommand::Reattach => {
+                        if !controller.is_running() {
+                            return None;
+                        }
+                        match controller.restart() {
+                            Ok(()) => {
+                                Ok("Running (reattached after session reconnect)".to_string())
+                            }
+                            Err(err) => Err(anyhow!("reattach failed: {}", err)),
+                        }
+                    }
+                    GuiCommand::Close => {
+                        // The window is going away, so the summary cannot
+                        // wait in the status line.
+                        if let Ok(Some(summary)) = controller.finish("window closed") {
+                            nwg::simple_message("KeepActive session summary", &summary.text());
+                        }
+                        nwg::stop_thread_dispatch();
+                        return None;
+                    }
+                };
+                (result, was_running, controller.is_running())
+            };
+            state.start_btn.set_enabled(!running);
+            state.stop_btn.set_enabled(running);
+            if let Some(tray) = &state.tray {
+                tray.start.set_enabled(!running);
+                tray.stop.set_enabled(running);
+                if running != was_running {
+                    tray.announce(running);
+                }
+            }
+            match result {
+                Ok(status) => {
+                    state.status_label.set_text(&format!("Status: {}", status));
+                    None
+                }
+                Err(err) => {
+                    let message = format!("Error: {}", err);
+                    state.status_label.set_text(&format!("Status: {}", message));
+                    Some(message)
+                }
+            }
+        }
+    }
+
+    let state = Rc::new(GuiState {
+        window_list,
+        window_remove_btn,
+        pick_btn,
+        click_btn,
+        exe_list,
+        exe_remove_btn,
+        target_entry,
+        add_btn,
+        status_label,
+        start_btn,
+        stop_btn,
+        tray,
+        click_capture: RefCell::new(None),
+    });
+    let commands = Rc::new(GuiCommands {
+        controller: RefCell::new(KeepAliveController::reporting_to(Arc::clone(&status_board))),
+        state: Rc::clone(&state),
+        pending: RefCell::new(VecDeque::new()),
+        draining: Cell::new(false),
+    });
+
+    // The Start button, the tray menu and the hotkey all start the same way.
+    let start_activation = {
+        let ui_state = Rc::clone(&state);
+        let commands = Rc::clone(&commands);
+        let base_config = config.clone();
+        let preview = config.preview;
+        let window_handle = window.handle;
+        Rc::new(move || {
+            let window_titles = {
+                let col = ui_state.window_list.collection();
+                col.iter().cloned().collect::<Vec<_>>()
+            };
+            let process_names = {
+                let col = ui_state.exe_list.collection();
+                col.iter().cloned().collect::<Vec<_>>()
+            };
+
+            let config = AppConfig {
+                window_titles,
+                process_names,
+                ..base_config.clone()
+            }
+            .resolved();
+
+            if preview {
+                let lines = format_preview(&preview_targets(&config), &quoted_title);
+                let params = nwg::MessageParams {
+                    title: "Start KeepActive?",
+                    content: &format!("Resolved targets:\r\n{}", lines.join("\r\n")),
+                    buttons: nwg::MessageButtons::YesNo,
+                    icons: nwg::MessageIcons::Question,
+                };
+                if nwg::modal_message(window_handle, &params) != nwg::MessageChoice::Yes {
+                    return;
+                }
+            }
+
+            commands.submit(GuiCommand::Start(Box::new(config)));
+        })
+    };
+
+    // An RDP reconnect can hand the session a new input desktop and stale
+    // window handles, so running workers are replaced wholesale.
+    let main_hwnd = HWND(window.handle.hwnd().unwrap_or(std::ptr::null_mut()).cast());
+    unsafe {
+        let _ = WTSRegisterSessionNotification(main_hwnd, NOTIFY_FOR_THIS_SESSION);
+    }
+    let raw_state = Rc::clone(&state);
+    let raw_commands = Rc::clone(&commands);
+    let raw_start = Rc::clone(&start_activation);
+    let session_handler = nwg::bind_raw_event_handler(
+        &window.handle,
+        SESSION_HANDLER_ID,
+        move |_, msg, wparam, lparam| {
+            if msg == WM_WTSSESSION_CHANGE
+                && matches!(wparam as u32, WTS_REMOTE_CONNECT | WTS_CONSOLE_CONNECT)
+            {
+                raw_commands.submit(GuiCommand::Reattach);
+            } else if msg == WM_HOTKEY && wparam as i32 == TOGGLE_HOTKEY_ID {
+                // The Stop button is enabled exactly while activation runs.
+                if raw_state.stop_btn.enabled() {
+                    raw_commands.submit(GuiCommand::Stop);
+                } else {
+                    raw_start();
+                }
+            } else if msg == WM_CLICK_CAPTURED {
+                let point = POINT {
+                    x: wparam as isize as i32,
+                    y: lparam as i32,
+                };
+                raw_state.finish_click_capture(Some(point));
+            } else if msg == WM_CLICK_CANCELLED {
+                raw_state.finish_click_capture(None);
+            }
+            None
+        },
+    )
+    .context("failed to bind session change, hotkey and click capture handler")?;
+    let _session_guard = SessionNotificationGuard {
+        hwnd: main_hwnd,
+        handler: Some(session_handler),
+    };
+
+    let hotkey_registered = match config.hotkey {
+        Hotkey::Keys { modifiers, vk } => unsafe {
+            RegisterHotKey(main_hwnd, TOGGLE_HOTKEY_ID, modifiers | MOD_NOREPEAT, vk).is_ok()
+        },
+        Hotkey::Off => false,
+    };
+    if config.hotkey != Hotkey::Off && !hotkey_registered {
+        let status = state.status_label.text();
+        state.status_label.set_text(&format!("{} (hotkey {} is taken)", status, config.hotkey));
+    }
+    let _hotkey_guard = HotkeyGuard {
+        hwnd: main_hwnd,
+        registered: hotkey_registered,
+    };
+
+    let ui_state = Rc::clone(&state);
+    let picker_for_events = Rc::clone(&picker);
+    let status_notice_handle = status_notice.handle;
+    let run_timer_handle = run_timer.handle;
+    let worker_timer_handle = worker_timer.handle;
+    let tray_handle = ui_state.tray.as_ref().map(|tray| tray.notification.handle);
+    let handler = nwg::full_bind_event_handler(&window.handle, move |evt, evt_data, handle| {
+        use nwg::{Event, EventData, MousePressEvent};
+
+        let tray = ui_state.tray.as_ref();
+        let is_tray_item = |item: fn(&TrayIcon) -> &nwg::MenuItem| {
+            tray.is_some_and(|tray| handle == item(tray).handle)
+        };
+        match evt {
+            Event::OnButtonClick | Event::OnMenuItemSelected => {
+                if handle == ui_state.start_btn.handle || is_tray_item(|tray| &tray.start) {
+                    start_activation();
+                } else if handle == ui_state.stop_btn.handle || is_tray_item(|tray| &tray.stop) {
+                    commands.submit(GuiCommand::Stop);
+                } else if is_tray_item(|tray| &tray.exit) {
+                    ui_state.save_lists();
+                    commands.submit(GuiCommand::Close);
+                } else if handle == ui_state.add_btn.handle {
+                    let entry_text = ui_state.target_entry.text();
+                    let trimmed = entry_text.trim();
+                    if trimmed.is_empty() {
+                        ui_state.target_entry.set_text("");
+                    } else {
+                        let entry_owned = trimmed.to_string();
+                        let lower = entry_owned.to_ascii_lowercase();
+                        if lower.ends_with(".exe") {
+                            add_to_list(&ui_state.exe_list, entry_owned);
+                        } else {
+                            add_to_list(&ui_state.window_list, entry_owned);
+                        }
+                        ui_state.target_entry.set_text("");
+                    }
+                } else if handle == ui_state.pick_btn.handle {
+                    picker.open();
+                } else if handle == ui_state.click_btn.handle {
+                    if ui_state.click_capture.borrow().is_none() {
+                        match ClickCapture::begin(main_hwnd) {
+                            Some(capture) => {
+                                let status = ui_state.status_label.text();
+                                ui_state.status_label.set_text(
+                                    "Status: Click a window to add it (Esc cancels)",
+                                );
+                                *ui_state.click_capture.borrow_mut() = Some((capture, status));
+                            }
+                            None => ui_state
+                                .status_label
+                                .set_text("Status: Error: could not capture mouse clicks"),
+                        }
+                    }
+                } else if handle == ui_state.window_remove_btn.handle {
+                    if let Some(index) = ui_state.window_list.selection() {
+                        ui_state.window_list.remove(index);
+                    }
+                } else if handle == ui_state.exe_remove_btn.handle
+                    && let Some(index) = ui_state.exe_list.selection()
+                {
+                    ui_state.exe_list.remove(index);
+                }
+            }
+            // Worker reports only mean something while activation runs.
+            Event::OnNotice if handle == status_notice_handle => {
+                if ui_state.stop_btn.enabled()
+                    && let Some(summary) = status_board.summary()
+                {
+                    ui_state.status_label.set_text(&format!("Status: {}", summary));
+                }
+            }
+            Event::OnTimerTick if handle == run_timer_handle => {
+                commands.submit(GuiCommand::RunLimitReached);
+            }
+            Event::OnTimerTick if handle == worker_timer_handle => {
+                commands.submit(GuiCommand::CheckWorkers);
+            }
+            Event::OnContextMenu if Some(handle) == tray_handle => {
+                if let Some(tray) = tray {
+                    let (x, y) = nwg::GlobalCursor::position();
+                    tray.menu.popup(x, y);
+                }
+            }
+            Event::OnMousePress(MousePressEvent::MousePressLeftUp)
+                if Some(handle) == tray_handle =>
+            {
+                unsafe {
+                    let _ = ShowWindow(main_hwnd, SW_SHOWNORMAL);
+                    let _ = SetForegroundWindow(main_hwnd);
+                }
+            }
+            // With a tray icon, closing only hides the window; Exit in the
+            // tray menu is what quits.
+            Event::OnWindowClose if tray.is_some() => {
+                if let EventData::OnWindowClose(data) = evt_data {
+                    data.close(false);
+                }
+                let _ = unsafe { ShowWindow(main_hwnd, SW_HIDE) };
+            }
+            Event::OnWindowClose => {
+                ui_state.save_lists();
+                commands.submit(GuiCommand::Close);
+            }
+            _ => {}
+        }
+    });
+
+    let _guard = EventHandlerGuard { handler: Some(handler) };
+
+    let picker_state = Rc::clone(&state);
+    let picker_window = picker_for_events.window.handle;
+    let picker_handler = nwg::full_bind_event_handler(&picker_window, move |evt, evt_data, handle| {
+        use nwg::{Event, EventData};
+
+        let picker = &picker_for_events;
+        match evt {
+            Event::OnButtonClick if handle == picker.add_title.handle => {
+                if let Some((title, _)) = picker.selected() {
+                    add_to_list(&picker_state.window_list, title);
+                    picker.window.set_visible(false);
+                }
+            }
+            Event::OnButtonClick if handle == picker.add_exe.handle => {
+                if let Some((_, exe)) = picker.selected().filter(|(_, exe)| !exe.is_empty()) {
+                    add_to_list(&picker_state.exe_list, exe);
+                    picker.window.set_visible(false);
+                }
+            }
+            Event::OnButtonClick if handle == picker.close.handle => {
+                picker.window.set_visible(false);
+            }
+            // Hide rather than destroy, so the picker can be opened again.
+            Event::OnWindowClose => {
+                if let EventData::OnWindowClose(data) = evt_data {
+                    data.close(false);
+                }
+                picker.window.set_visible(false);
+            }
+            _ => {}
+        }
+    });
+    let _picker_guard = EventHandlerGuard { handler: Some(picker_handler) };
+
+    nwg::dispatch_thread_events();
+    Ok(())
+}
+
+/// Adds `entry` to a GUI list unless it is already there (ignoring case),
+/// and selects it either way.
+fn add_to_list(list: &nwg::ListBox<String>, entry: String) {
+    let existing = list.collection().iter().position(|value| value.eq_ignore_ascii_case(&entry));
+    let index = existing.unwrap_or_else(|| {
+        list.push(entry);
+        list.len().saturating_sub(1)
+    });
+    list.set_selection(Some(index));
+}
+
+/// The "Pick Window" dialog: every visible, titled top-level window with its
+/// executable. Built once, hidden when closed, and refilled on every open so
+/// apps started in the meantime show up.
+struct WindowPicker {
+    window: nwg::Window,
+    list: nwg::ListBox<String>,
+    add_title: nwg::Button,
+    add_exe: nwg::Button,
+    close: nwg::Button,
+    entries: RefCell<Vec<(String, String)>>,
+}
+
+impl WindowPicker {
+    fn build(owner: &nwg::Window, icon: Option<&nwg::Icon>) -> Result<Self, nwg::NwgError> {
+        let mut picker = Self {
+            window: Default::default(),
+            list: Default::default(),
+            add_title: Default::default(),
+            add_exe: Default::default(),
+            close: Default::default(),
+            entries: RefCell::new(Vec::new()),
+        };
+        nwg::Window::builder()
+            .flags(nwg::WindowFlags::WINDOW)
+            .size((520, 400))
+            .title("Pick a window")
+            .icon(icon)
+            .parent(Some(&owner.handle))
+            .build(&mut picker.window)?;
+        nwg::ListBox::builder()
+            .position((20, 20))
+            .size((480, 310))
+            .parent(&picker.window)
+            .build(&mut picker.list)?;
+        nwg::Button::builder()
+            .text("Add Title")
+            .position((20, 344))
+            .size((150, 32))
+            .parent(&picker.window)
+            .build(&mut picker.add_title)?;
+        nwg::Button::builder()
+            .text("Add Executable")
+            .position((185, 344))
+            .size((150, 32))
+            .parent(&picker.window)
+            .build(&mut picker.add_exe)?;
+        nwg::Button::builder()
+            .text("Close")
+            .position((350, 344))
+            .size((150, 32))
+            .parent(&picker.window)
+            .build(&mut picker.close)?;
+        Ok(picker)
+    }
+
+    fn open(&self) {
+        let entries: Vec<(String, String)> =
+            pickable_windows().into_iter().map(|(info, exe)| (info.title, exe)).collect();
+        let lines = entries
+            .iter()
+            .map(|(title, exe)| {
+                if exe.is_empty() { title.clone() } else { format!("{}  [{}]", title, exe) }
+            })
+            .collect();
+        self.list.set_collection(lines);
+        *self.entries.borrow_mut() = entries;
+        self.window.set_visible(true);
+        self.window.set_focus();
+    }
+
+    /// The highlighted window's title and executable name.
+    fn selected(&self) -> Option<(String, String)> {
+        let index = self.list.selection()?;
+        self.entries.borrow().get(index).cloned()
+    }
+}
+
+/// The GUI's two lists, saved to `gui-state.toml` in the app data folder
+/// when the window closes and put back on the next launch.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct GuiLists {
+    window_titles: Vec<String>,
+    process_names: Vec<String>,
+}
+
+impl GuiLists {
+    fn path() -> PathBuf {
+        app_data_dir().join("gui-state.toml")
+    }
+
+    /// A missing, unreadable or corrupt file (say, one cut short by a crash
+    /// mid-write) just means there is nothing to restore.
+    fn load() -> Option<Self> {
+        Self::parse(&fs::read_to_string(Self::path()).ok()?)
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        toml::from_str(text).ok()
+    }
+
+    /// Best effort: a failed save only costs the lists on the next launch.
+    fn save(&self) {
+        let path = Self::path();
+        let Ok(text) = toml::to_string(self) else {
+            return;
+        };
+        if let Some(dir) = path.parent()
+            && fs::create_dir_all(dir).is_ok()
+        {
+            let _ = fs::write(&path, text);
+        }
+    }
+}
+
+/// The GUI's notification-area icon and its right-click menu.
+struct TrayIcon {
+    notification: nwg::TrayNotification,
+    menu: nwg::Menu,
+    start: nwg::MenuItem,
+    stop: nwg::MenuItem,
+    exit: nwg::MenuItem,
+}
+
+impl TrayIcon {
+    fn build(window: &nwg::Window, icon: &nwg::Icon) -> Result<Self, nwg::NwgError> {
+        let mut tray = Self {
+            notification: Default::default(),
+            menu: Default::default(),
+            start: Default::default(),
+            stop: Default::default(),
+            exit: Default::default(),
+        };
+        nwg::TrayNotification::builder()
+            .parent(window)
+            .icon(Some(icon))
+            .tip(Some("KeepActive - not running"))
+            .build(&mut tray.notification)?;
+        nwg::Menu::builder().popup(true).parent(window).build(&mut tray.menu)?;
+        nwg::MenuItem::builder().text("Start").parent(&tray.menu).build(&mut tray.start)?;
+        nwg::MenuItem::builder()
+            .text("Stop")
+            .disabled(true)
+            .parent(&tray.menu)
+            .build(&mut tray.stop)?;
+        nwg::MenuItem::builder().text("Exit").parent(&tray.menu).build(&mut tray.exit)?;
+        Ok(tray)
+    }
+
+    /// Balloon and tooltip for activation starting or stopping.
+    fn announce(&self, running: bool) {
+        let (text, tip) = if running {
+            ("Activation started", "KeepActive - running")
+        } else {
+            ("Activation stopped", "KeepActive - not running")
+        };
+        let flags = nwg::TrayNotificationFlags::INFO_ICON | nwg::TrayNotificationFlags::QUIET;
+        self.notification.show(text, Some("KeepActive"), Some(flags), None);
+        self.notification.set_tip(tip);
+    }
+}
+
+struct SessionNotificationGuard {
+    hwnd: HWND,
+    handler: Option<nwg::RawEventHandler>,
+}
+
+impl Drop for SessionNotificationGuard {
+    fn drop(&mut self) {
+        if let Some(handler) = self.handler.take() {
+            let _ = nwg::unbind_raw_event_handler(&handler);
+        }
+        unsafe {
+            let _ = WTSUnRegisterSessionNotification(self.hwnd);
+        }
+    }
+}
+
+struct HotkeyGuard {
+    hwnd: HWND,
+    registered: bool,
+}
+
+impl Drop for HotkeyGuard {
+    fn drop(&mut self) {
+        if self.registered {
+            unsafe {
+                let _ = UnregisterHotKey(self.hwnd, TOGGLE_HOTKEY_ID);
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// Where the click capture hooks report to; set only while one runs.
+    static CLICK_CAPTURE_TARGET: Cell<Option<HWND>> = const { Cell::new(None) };
+}
+
+/// "Click a Window" capture mode. Low-level hooks swallow the next left click,
+/// so the clicked app never sees it, and Escape, and post them to the GUI
+/// window. Only a system cursor shows over other apps' windows, so the arrow
+/// is swapped for the crosshair meanwhile. Dropping it unhooks and puts the
+/// user's cursors back.
+struct ClickCapture {
+    mouse: HHOOK,
+    keyboard: HHOOK,
+}
+
+impl ClickCapture {
+    fn begin(notify: HWND) -> Option<Self> {
+        let module = unsafe { GetModuleHandleW(None) }.ok()?;
+        let mouse =
+            unsafe { SetWindowsHookExW(WH_MOUSE_LL, Some(click_capture_mouse), module, 0) }.ok()?;
+        let keyboard =
+            unsafe { SetWindowsHookExW(WH_KEYBOARD_LL, Some(click_capture_keyboard), module, 0) };
+        let Ok(keyboard) = keyboard else {
+            let _ = unsafe { UnhookWindowsHookEx(mouse) };
+            return None;
+        };
+        CLICK_CAPTURE_TARGET.set(Some(notify));
+        // SetSystemCursor takes ownership of the cursor, hence the copy.
+        unsafe {
+            if let Ok(cross) = LoadCursorW(HINSTANCE::default(), IDC_CROSS)
+                && let Ok(copy) = CopyIcon(HICON(cross.0))
+            {
+                let _ = SetSystemCursor(HCURSOR(copy.0), OCR_NORMAL);
+            }
+        }
+        Some(Self { mouse, keyboard })
+    }
+}
+
+impl Drop for ClickCapture {
+    fn drop(&mut self) {
+        CLICK_CAPTURE_TARGET.set(None);
+        unsafe {
+            let _ = UnhookWindowsHookEx(self.mouse);
+            let _ = UnhookWindowsHookEx(self.keyboard);
+            let _ = SystemParametersInfoW(SPI_SETCURSORS, 0, None, Default::default());
+        }
+    }
+}
+
+unsafe extern "system" fn click_capture_mouse(
+    code: i32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if code == HC_ACTION as i32
+        && let Some(target) = CLICK_CAPTURE_TARGET.get()
+    {
+        match wparam.0 as u32 {
+            WM_LBUTTONDOWN => return LRESULT(1),
+            WM_LBUTTONUP => {
+                let point = unsafe { (*(lparam.0 as *const MSLLHOOKSTRUCT)).pt };
+                let (x, y) = (WPARAM(point.x as isize as usize), LPARAM(point.y as isize));
+                let _ = unsafe { PostMessageW(target, WM_CLICK_CAPTURED, x, y) };
+                return LRESULT(1);
+            }
+            _ => {}
+        }
+    }
+    unsafe { CallNextHookEx(HHOOK::default(), code, wparam, lparam) }
+}
+
+unsafe extern "system" fn click_capture_keyboard(
+    code: i32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if code == HC_ACTION as i32
+        && wparam.0 as u32 == WM_KEYDOWN
+        && let Some(target) = CLICK_CAPTURE_TARGET.get()
+        && unsafe { (*(lparam.0 as *const KBDLLHOOKSTRUCT)).vkCode } == u32::from(VK_ESCAPE.0)
+    {
+        let _ = unsafe { PostMessageW(target, WM_CLICK_CANCELLED, WPARAM(0), LPARAM(0)) };
+        return LRESULT(1);
+    }
+    unsafe { CallNextHookEx(HHOOK::default(), code, wparam, lparam) }
+}
+
+struct EventHandlerGuard {
+    handler: Option<nwg::EventHandler>,
+}
+
+impl Drop for EventHandlerGuard {
+    fn drop(&mut self) {
+        if let Some(handler) = self.handler.take() {
+            nwg::unbind_event_handler(&handler);
+        }
+    }
+}
+
+fn worker_loop(
+    active: Arc<AtomicBool>,
+    config: ResolvedConfig,
+    verbose: bool,
+    mut status: Option<StatusReporter>,
+    log: Option<&WorkerLog>,
+) {
+    let mut iteration: u64 = 0;
+    let mut logged_target: Option<HWND> = None;
+    let mut jitter = Jitter::new(config.options.jitter_ms);
+    let started = Instant::now();
+    // Only the worker for the executable's own rule launches it.
+    let launch = config.options.launch.as_ref().filter(|launch| {
+        let name = launch.exe_name();
+        config.process_names.iter().any(|rule| rule.eq_ignore_ascii_case(&name))
+    });
+    let mut last_launch: Option<Instant> = None;
+    let mut burst_sent: u32 = 0;
+    let mut last_sent: Vec<(HWND, bool)> = Vec::new();
+    let mut ladder = config.options.escalate.map(EscalationLadder::new);
+    let mut detector = config.options.auto_method.then(MethodDetector::default);
+    let mut latencies = LatencyHistogram::default();
+    let mut last_jiggle: Option<Instant> = None;
+    let mut last_key: Option<Instant> = None;
+    let mut pacer = config.options.pacing.map(|(min, max)| Pacer::new(min, max));
+    let audit = config
+        .options
+        .audit_csv
+        .clone()
+        .map(|path| AuditLog::new(path, &config));
+    let record = |hwnd: Option<HWND>, action: &str, result: Option<isize>| {
+        if let Some(audit) = &audit {
+            audit.record(hwnd, action, result);
+        }
+    };
+    'poll: while active.load(Ordering::SeqCst) {
+        iteration += 1;
+        if config.options.expired(started) {
+            if verbose {
+                println!("[{}] --max-duration reached, stopping", iteration);
+            }
+            record(None, "max-duration", None);
+            break;
+        }
+        if config.options.pause_when_locked && is_workstation_locked() {
+            if verbose {
+                println!("[{}] workstation locked, skipping", iteration);
+            }
+            record(None, "locked", None);
+            sleep_while_active(&active, jitter.apply(config.options.interval()));
+            continue;
+        }
+        if let Some(schedule) = config.options.schedule
+            && let Some(wait) = schedule.wait()
+        {
+            if verbose {
+                println!(
+                    "[{}] outside {}, checking again in {}s",
+                    iteration,
+                    schedule,
+                    wait.as_secs()
+                );
+            }
+            record(None, "outside-schedule", None);
+            sleep_while_active(&active, wait);
+            continue;
+        }
+        if let Some(idle) = within_input_grace(&config.options) {
+            if verbose {
+                println!("[{}] input {}ms ago, within grace", iteration, idle.as_millis());
+            }
+            record(None, "input-grace", None);
+            sleep_while_active(&active, jitter.apply(config.options.interval()));
+            continue;
+        }
+        let targets = resolve_targets(&config);
+        if let Some(status) = &mut status {
+            status.report(targets.first().copied());
+        }
+        if let Some(log) = log
+            && targets.first() != logged_target.as_ref()
+        {
+            match targets.first() {
+                Some(&hwnd) => log.line(&format!(
+                    "found {:?} {}",
+                    hwnd.0,
+                    quoted_title(&window_title(hwnd))
+                )),
+                None => log.line("lost the target, searching"),
+            }
+            logged_target = targets.first().copied();
+        }
+        if targets.is_empty() {
+            last_sent.clear();
+            if verbose {
+                println!("[{}] no target window found", iteration);
+            }
+            record(None, "not-found", None);
+            if let Some(launch) = launch
+                && last_launch.is_none_or(|at| at.elapsed() >= LaunchTarget::DEBOUNCE)
+                && !launch.is_running()
+            {
+                last_launch = Some(Instant::now());
+                let result = launch.spawn();
+                let outcome = match &result {
+                    Ok(()) => format!("launched {}", launch.path.display()),
+                    Err(err) => format!("failed to launch {}: {}", launch.path.display(), err),
+                };
+                if verbose {
+                    println!("[{}] {}", iteration, outcome);
+                }
+                if let Some(log) = log {
+                    log.line(&outcome);
+                }
+                record(None, if result.is_ok() { "launched" } else { "launch-failed" }, None);
+            }
+            sleep_while_active(&active, jitter.apply(config.options.interval()));
+            continue;
+        }
+        let mut states = Vec::new();
+        let mut sent = false;
+        if let Some(detector) = &mut detector {
+            detector.forget_except(&targets);
+        }
+        for &hwnd in &targets {
+            let foreground = unsafe { GetForegroundWindow() } == hwnd;
+            if config.options.only_when_background && foreground {
+                if verbose {
+                    println!(
+                        "[{}] target {:?} is in the foreground, skipping",
+                        iteration, hwnd.0
+                    );
+                }
+                record(Some(hwnd), "skipped-foreground", None);
+                // Keep the last sent state so a skipped window is not
+                // re-sent just for having been in the foreground meanwhile.
+                states.extend(last_sent.iter().filter(|(previous, _)| *previous == hwnd));
+                continue;
+            }
+            if config.options.only_when_playing && !window_is_playing(hwnd) {
+                if verbose {
+                    println!("[{}] target {:?} is not playing audio, skipping", iteration, hwnd.0);
+                }
+                record(Some(hwnd), "skipped-silent", None);
+                continue;
+            }
+            if title_suppressed(hwnd, &config.options) {
+                if verbose {
+                    println!(
+                        "[{}] target {:?} \"{}\" matches --suppress-while-title, skipping",
+                        iteration,
+                        hwnd.0,
+                        window_title(hwnd)
+                    );
+                }
+                record(Some(hwnd), "suppressed-title", None);
+                continue;
+            }
+            if config.options.coalesce {
+                states.push((hwnd, foreground));
+                if last_sent.contains(&(hwnd, foreground)) {
+                    if verbose {
+                        println!("[{}] target {:?} unchanged, coalesced", iteration, hwnd.0);
+                    }
+                    record(Some(hwnd), "coalesced", None);
+                    continue;
+                }
+            }
+            // A dry run stops before the first thing that touches the window.
+            if config.options.dry_run {
+                if verbose {
+                    println!(
+                        "[{}] would activate {:?} ({})",
+                        iteration,
+                        hwnd.0,
+                        quoted_title(&window_title(hwnd))
+                    );
+                }
+                record(Some(hwnd), "dry-run", None);
+                if let Some(log) = log {
+                    log.line(&format!("would activate {:?}", hwnd.0));
+                }
+                sent = true;
+                continue;
+            }
+            // Only restore while actually minimized, so a restored window is
+            // not sent SW_RESTORE every iteration.
+            if config.options.restore && unsafe { IsIconic(hwnd) }.as_bool() {
+                let _ = unsafe { ShowWindow(hwnd, SW_RESTORE) };
+                if verbose {
+                    println!("[{}] target {:?} was minimized, restored", iteration, hwnd.0);
+                }
+                record(Some(hwnd), "restored", None);
+            }
+            let started = Instant::now();
+            let granted = config.options.activate_method == ActivateMethod::Foreground
+                && attach_target_and_foreground(hwnd);
+            // A refused foreground request falls back to the message.
+            let result = if granted { 1 } else { send_activation(hwnd, &config.options) };
+            let latency = started.elapsed();
+            if let Some(status) = &mut status {
+                status.count(delivered(hwnd, granted, result, &config.options));
+            }
+            latencies.add(latency);
+            if let Some(pacer) = &mut pacer {
+                pacer.observe(latency);
+            }
+            if verbose {
+                let outcome = if granted {
+                    "took the foreground".to_string()
+                } else if config.options.activate_method == ActivateMethod::Foreground {
+                    format!("foreground refused, WM_ACTIVATE returned {}", result)
+                } else {
+                    format!("{} returned {}", activation_message(&config.options), result)
+                };
+                println!(
+                    "[{}] target {:?} \"{}\" -> {} in {}us",
+                    iteration,
+                    hwnd.0,
+                    window_title(hwnd),
+                    outcome,
+                    latency.as_micros()
+                );
+                if latencies.total() % LATENCY_REPORT_EVERY == 0 {
+                    println!("[{}] latency {}", iteration, latencies);
+                }
+            }
+            if let Some(log) = log {
+                let how = if granted {
+                    "brought to the foreground".to_string()
+                } else {
+                    format!("sent {}", activation_message(&config.options))
+                };
+                log.line(&format!("{:?} {} -> {}", hwnd.0, how, result));
+            }
+            if granted {
+                record(Some(hwnd), "foreground", Some(result));
+            } else {
+                if config.options.activate_method == ActivateMethod::Foreground {
+                    record(Some(hwnd), "foreground-refused", None);
+                }
+                record(Some(hwnd), "sent", Some(result));
+            }
+            if let Some(delay) = config.options.double_tap_ms {
+                sleep_while_active(&active, Duration::from_millis(delay));
+                if !active.load(Ordering::SeqCst) {
+                    break 'poll;
+                }
+                let result = send_activation(hwnd, &config.options);
+                if let Some(status) = &mut status {
+                    status.count(delivered(hwnd, false, result, &config.options));
+                }
+                if verbose {
+                    println!(
+                        "[{}] double tap after {}ms -> {} returned {}",
+                        iteration,
+                        delay,
+                        activation_message(&config.options),
+                        result
+                    );
+                }
+                record(Some(hwnd), "double-tap", Some(result));
+            }
+            sent = true;
+            if config.options.jiggle_cursor
+                && foreground
+                && last_jiggle.is_none_or(|at| at.elapsed() >= JIGGLE_INTERVAL)
+            {
+                jiggle_cursor();
+                last_jiggle = Some(Instant::now());
+                record(Some(hwnd), "jiggle", None);
+            }
+            if let Some((vk, method)) = config.options.keepalive_key
+                && last_key.is_none_or(|at| at.elapsed() >= KEEPALIVE_KEY_INTERVAL)
+            {
+                let pressed = press_keepalive_key(hwnd, vk, method);
+                if verbose {
+                    let outcome = match pressed {
+                        Ok(path) => format!("sent with {}", path.name()),
+                        Err(reason) => reason.to_string(),
+                    };
+                    println!("[{}] keep-alive key {} {}", iteration, key_name(vk), outcome);
+                }
+                if pressed.is_ok() {
+                    last_key = Some(Instant::now());
+                    record(Some(hwnd), "key", None);
+                }
+            }
+            if let Some(detector) = &mut detector {
+                match detector.step(hwnd) {
+                    MethodEvent::Using => {}
+                    MethodEvent::Detected(method) => {
+                        if verbose {
+                            println!(
+                                "[{}] target {:?} \"{}\" reaches the foreground with {}",
+                                iteration,
+                                hwnd.0,
+                                window_title(hwnd),
+                                method
+                            );
+                        }
+                        record(Some(hwnd), &format!("detected-{}", method), None);
+                    }
+                    MethodEvent::NoneWorked => {
+                        if verbose {
+                            println!(
+                                "[{}] no method brought {:?} to the foreground, retrying in {}s",
+                                iteration,
+                                hwnd.0,
+                                ESCALATION_BACKOFF.as_secs()
+                            );
+                        }
+                        record(Some(hwnd), "method-none", None);
+                    }
+                    MethodEvent::Failing(method) => {
+                        if verbose {
+                            println!(
+                                "[{}] {} stopped working for {:?}, detecting again",
+                                iteration, method, hwnd.0
+                            );
+                        }
+                        record(Some(hwnd), "method-failing", None);
+                    }
+                }
+            }
+            if let Some(ladder) = &mut ladder {
+                match ladder.step(hwnd) {
+                    LadderStep::Holding => {}
+                    LadderStep::Escalated(method, granted) => {
+                        if verbose {
+                            println!(
+                                "[{}] still not foreground, escalated to {} -> {}",
+                                iteration,
+                                method,
+                                if granted { "granted" } else { "refused" }
+                            );
+                        }
+                        record(Some(hwnd), method, Some(granted as isize));
+                    }
+                    LadderStep::BackOff => {
+                        if verbose {
+                            println!(
+                                "[{}] every method failed, backing off for {}s",
+                                iteration,
+                                ESCALATION_BACKOFF.as_secs()
+                            );
+                        }
+                        record(Some(hwnd), "escalation-backoff", None);
+                        sleep_while_active(&active, ESCALATION_BACKOFF);
+                        continue 'poll;
+                    }
+                }
+            }
+        }
+        last_sent = states;
+        if sent
+            && let Some(pacer) = &mut pacer
+            && let Some(interval) = pacer.adjust()
+        {
+            if verbose {
+                println!(
+                    "[{}] smoothed latency {}ms, now pacing every {}ms",
+                    iteration,
+                    pacer.smoothed.as_millis(),
+                    interval.as_millis()
+                );
+            }
+            record(None, "pace", Some(interval.as_millis() as isize));
+        }
+        if sent && let Some(burst) = config.options.burst {
+            burst_sent += 1;
+            if burst_sent >= burst {
+                burst_sent = 0;
+                if verbose {
+                    println!(
+                        "[{}] burst of {} done, cooling down for {}s",
+                        iteration, burst, config.options.cooldown_secs
+                    );
+                }
+                record(None, "cooldown", None);
+                sleep_while_active(&active, Duration::from_secs(config.options.cooldown_secs));
+                continue;
+            }
+        }
+        match &pacer {
+            Some(pacer) => sleep_while_active(&active, jitter.apply(pacer.interval)),
+            None => sleep_while_active(&active, jitter.apply(config.options.interval())),
+        }
+    }
+    if let Some(status) = &status {
+        status.finish();
+    }
+}
+
+/// `--report-status`: about once a second, tells the controller on stdout
+/// whether the worker has a window and how many activations it has sent so
+/// far, as `found<TAB><sent><TAB><failed><TAB><title>` or
+/// `searching<TAB><sent><TAB><failed>`.
+#[derive(Default)]
+struct StatusReporter {
+    last: Option<Instant>,
+    target: Option<HWND>,
+    counts: ActivationCounts,
+}
+
+impl StatusReporter {
+    const EVERY: Duration = Duration::from_secs(1);
+
+    fn report(&mut self, target: Option<HWND>) {
+        self.target = target;
+        if self.last.is_some_and(|at| at.elapsed() < Self::EVERY) {
+            return;
+        }
+        self.last = Some(Instant::now());
+        self.write();
+    }
+
+    /// The last line, written when the worker shuts down so the controller
+    /// sees every activation it sent.
+    fn finish(&self) {
+        self.write();
+    }
+
+    fn write(&self) {
+        let ActivationCounts { sent, failed } = self.counts;
+        let line = match self.target {
+            Some(hwnd) => format!(
+                "found\t{}\t{}\t{}",
+                sent,
+                failed,
+                window_title(hwnd).replace(['\t', '\r', '\n'], " ")
+            ),
+            None => format!("searching\t{}\t{}", sent, failed),
+        };
+        // Nobody may be listening any more; that is not the worker's problem.
+        let _ = writeln!(io::stdout(), "{}", line);
+    }
+
+    fn count(&mut self, delivered: bool) {
+        self.counts.sent += 1;
+        if !delivered {
+            self.counts.failed += 1;
+        }
+    }
+}
+
+/// A `StatusReporter` line as the controller reads it: the found window's
+/// title, if any, and the counts. Missing or garbled counts read as zero.
+fn parse_status_line(line: &str) -> (Option<String>, ActivationCounts) {
+    let mut fields = line.splitn(4, '\t');
+    let found = fields.next() == Some("found");
+    let mut count = || fields.next().and_then(|field| field.parse().ok()).unwrap_or(0);
+    let counts = ActivationCounts {
+        sent: count(),
+        failed: count(),
+    };
+    (found.then(|| fields.next().unwrap_or_default().to_string()), counts)
+}
+
+/// `--audit-csv` writer. Every worker appends to the same file, one
+/// `write_all` per row, so rows from different workers interleave whole.
+/// Writing is best effort: a hidden worker has nowhere to report failures.
+struct AuditLog {
+    path: PathBuf,
+    target: String,
+}
+
+impl AuditLog {
+    const HEADER: &'static str = "timestamp,target,hwnd,pid,title,foreground,action,result\n";
+
+    fn new(path: PathBuf, config: &ResolvedConfig) -> Self {
+        Self {
+            path,
+            target: rule_description(config),
+        }
+    }
+
+    fn record(&self, hwnd: Option<HWND>, action: &str, result: Option<isize>) {
+        let mut row = vec![utc_timestamp(), csv_field(&self.target)];
+        match hwnd {
+            Some(hwnd) => {
+                let mut pid = 0;
+                unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+                row.push(format!("{:?}", hwnd.0));
+                row.push(pid.to_string());
+                row.push(csv_field(&window_title(hwnd)));
+                row.push((unsafe { GetForegroundWindow() } == hwnd).to_string());
+            }
+            None => row.extend(std::iter::repeat_n(String::new(), 4)),
+        }
+        row.push(action.to_string());
+        row.push(result.map(|value| value.to_string()).unwrap_or_default());
+        let _ = self.append(&(row.join(",") + "\n"));
+    }
+
+    fn append(&self, line: &str) -> io::Result<()> {
+        if fs::metadata(&self.path).is_ok_and(|meta| meta.len() >= AUDIT_MAX_BYTES) {
+            let mut rotated = self.path.clone().into_os_string();
+            rotated.push(".1");
+            // Another worker may have rotated first; either way a fresh file follows.
+            let _ = fs::rename(&self.path, rotated);
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let text = if file.metadata()?.len() == 0 {
+            format!("{}{}", Self::HEADER, line)
+        } else {
+            line.to_string()
+        };
+        file.write_all(text.as_bytes())
+    }
+}
+
+/// The rule a worker was started for, as the audit log and worker log name it.
+fn rule_description(config: &ResolvedConfig) -> String {
+    if let Some(path) = config.hwnd_files.first() {
+        format!("hwnd file: {}", path.display())
+    } else if let Some(pair) = config.title_exe_pairs.first() {
+        format!("title '{}' in exe: {}", pair.title, pair.exe)
+    } else if let Some(rule) = config.class_matches.first() {
+        format!("class: {}", rule)
+    } else if let Some(name) = config.process_names.first() {
+        format!("exe: {}", name)
+    } else if let Some(pid) = config.pids.first() {
+        format!("pid: {}", pid)
+    } else if let Some(path) = config.exe_paths.first() {
+        format!("exe path: {}", path)
+    } else if let Some(fragment) = config.path_fragments.first() {
+        format!("exe path contains: {}", fragment)
+    } else if let Some(pattern) = config.title_regexes.first() {
+        format!("title regex: {}", pattern)
+    } else {
+        format!("window: {}", config.window_titles.join(", "))
+    }
+}
+
+/// `--log-file`: one timestamped line per worker event. Every line is
+/// written straight through, so nothing is lost if the worker is killed.
+struct WorkerLog {
+    file: fs::File,
+}
+
+impl WorkerLog {
+    fn open(path: &Path) -> Option<Self> {
+        let file = fs::OpenOptions::new().create(true).append(true).open(path).ok()?;
+        Some(Self { file })
+    }
+
+    fn line(&self, text: &str) {
+        let _ = (&self.file).write_all(format!("{} {}\n", utc_timestamp(), text).as_bytes());
+    }
+}
+
+/// Gives every worker its own log: `keepactive.log` becomes
+/// `keepactive-3.log` for worker 3.
+fn worker_log_path(path: &Path, id: usize) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(format!("-{}", id));
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn utc_timestamp() -> String {
+    let now = unsafe { GetSystemTime() };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        now.wYear, now.wMonth, now.wDay, now.wHour, now.wMinute, now.wSecond, now.wMilliseconds
+    )
+}
+
+/// Whose turn `--rotate` gives next: the first entry after `after` that has
+/// a window, wrapping around.
+fn next_turn<T>(windows: &[Option<T>], after: Option<usize>) -> Option<usize> {
+    let start = after.map_or(0, |index| index + 1);
+    (0..windows.len())
+        .map(|step| (start + step) % windows.len())
+        .find(|&index| windows[index].is_some())
+}
+
+/// `--rotate --all`: the scanned window handles in a stable order, since
+/// bringing a window to the front reorders the z-order the scan returns, and
+/// where the rotation stands among them: at `last`, the last window given a
+/// turn, or just before where it would sort if it has since closed.
+fn stable_turn_order(mut handles: Vec<usize>, last: Option<usize>) -> (Vec<usize>, Option<usize>) {
+    handles.sort_unstable();
+    let cursor = last.and_then(|last| handles.iter().rposition(|&handle| handle <= last));
+    (handles, cursor)
+}
+
+/// Round-robin scheduler for `--rotate`: every slice, the next rule that
+/// currently resolves gets the foreground, and it keeps receiving the usual
+/// activation message until the slice ends. Rules that do not resolve are
+/// skipped and rejoin the rotation as soon as their window reappears.
+fn rotate_loop(active: Arc<AtomicBool>, config: ResolvedConfig, slice: Duration, verbose: bool) {
+    let mut iteration: u64 = 0;
+    let mut cursor: Option<usize> = None;
+    let mut last: Option<HWND> = None;
+    let mut current: Option<(HWND, Instant)> = None;
+    let mut steals = StealBudget::new(config.options.max_steals_per_min);
+    let mut jitter = Jitter::new(config.options.jitter_ms);
+    let started = Instant::now();
+    while active.load(Ordering::SeqCst) && !config.options.expired(started) {
+        iteration += 1;
+        if config.options.pause_when_locked && is_workstation_locked() {
+            if verbose {
+                println!("[{}] workstation locked, skipping", iteration);
+            }
+            sleep_while_active(&active, jitter.apply(config.options.interval()));
+            continue;
+        }
+        if let Some(wait) = config.options.schedule.and_then(|schedule| schedule.wait()) {
+            sleep_while_active(&active, wait);
+            continue;
+        }
+        if let Some(idle) = within_input_grace(&config.options) {
+            if verbose {
+                println!("[{}] input {}ms ago, within grace", iteration, idle.as_millis());
+            }
+            sleep_while_active(&active, jitter.apply(config.options.interval()));
+            continue;
+        }
+        let (windows, all_cursor) = if config.options.all_windows {
+            let scanned = find_all_target_windows(&config);
+            let (handles, cursor) = stable_turn_order(
+                scanned.iter().map(|hwnd| hwnd.0 as usize).collect(),
+                last.map(|hwnd| hwnd.0 as usize),
+            );
+            let windows = handles.into_iter().map(|handle| Some(HWND(handle as *mut c_void)));
+            (windows.collect(), Some(cursor))
+        } else {
+            (rule_windows(&config), None)
+        };
+        let due = current.is_none_or(|(hwnd, since)| {
+            since.elapsed() >= slice || !windows.contains(&Some(hwnd))
+        });
+        if due {
+            if let Some(all_cursor) = all_cursor {
+                cursor = all_cursor;
+            }
+            let next = next_turn(&windows, cursor);
+            current = next.and_then(|index| windows[index]).map(|hwnd| {
+                let outcome = if unsafe { GetForegroundWindow() } == hwnd {
+                    "already held"
+                } else if title_suppressed(hwnd, &config.options) {
+                    "withheld, title matches --suppress-while-title"
+                } else if !steals.available() {
+                    "withheld, focus-steal budget used up"
+                } else if bring_to_foreground(hwnd) {
+                    steals.record();
+                    "granted"
+                } else {
+                    "refused"
+                };
+                if verbose {
+                    println!(
+                        "[{}] rotating to {:?} \"{}\" -> foreground {}",
+                        iteration,
+                        hwnd.0,
+                        window_title(hwnd),
+                        outcome
+                    );
+                }
+                (hwnd, Instant::now())
+            });
+            if next.is_some() {
+                cursor = next;
+                last = current.map(|(hwnd, _)| hwnd);
+            } else if verbose {
+                println!("[{}] no target window found", iteration);
+            }
+        }
+        if let Some((hwnd, _)) = current {
+            send_activation(hwnd, &config.options);
+        }
+        sleep_while_active(&active, jitter.apply(config.options.interval()));
+    }
+}
+
+/// Worker loop for `--mirror` rules: each iteration, every rule whose source
+/// window is in the foreground sends the activation message to its target.
+/// Nothing is sent while the source is in the background or either window
+/// is missing; the target is never brought to the front.
+fn mirror_loop(active: Arc<AtomicBool>, config: ResolvedConfig, verbose: bool) {
+    let mut iteration: u64 = 0;
+    let mut mirroring = vec![false; config.mirrors.len()];
+    let mut jitter = Jitter::new(config.options.jitter_ms);
+    let started = Instant::now();
+    while active.load(Ordering::SeqCst) && !config.options.expired(started) {
+        iteration += 1;
+        if config.options.pause_when_locked && is_workstation_locked() {
+            sleep_while_active(&active, jitter.apply(config.options.interval()));
+            continue;
+        }
+        if let Some(wait) = config.options.schedule.and_then(|schedule| schedule.wait()) {
+            sleep_while_active(&active, wait);
+            continue;
+        }
+        let foreground = unsafe { GetForegroundWindow() };
+        for (mirror, was_mirroring) in config.mirrors.iter().zip(&mut mirroring) {
+            let source_focused = MirrorRule::resolve(&mirror.source, &config.options)
+                .is_some_and(|source| source == foreground);
+            let target = MirrorRule::resolve(&mirror.target, &config.options)
+                .filter(|&target| target != foreground);
+            let now_mirroring = source_focused && target.is_some();
+            if verbose && now_mirroring != *was_mirroring {
+                println!(
+                    "[{}] {}: {}",
+                    iteration,
+                    mirror,
+                    if now_mirroring { "source focused, mirroring" } else { "idle" }
+                );
+            }
+            *was_mirroring = now_mirroring;
+            if let (true, Some(target)) = (source_focused, target) {
+                send_activation(target, &config.options);
+            }
+        }
+        sleep_while_active(&active, jitter.apply(config.options.interval()));
+    }
+}
+
+/// `--launch`: an executable to start when no process of its name runs.
+#[derive(Clone, Debug)]
+struct LaunchTarget {
+    path: PathBuf,
+    args: Vec<String>,
+}
+
+impl LaunchTarget {
+    /// A crash right after start must not turn into a spawn storm.
+    const DEBOUNCE: Duration = Duration::from_secs(30);
+
+    fn exe_name_of(path: &Path) -> Option<String> {
+        path.file_name().map(|name| name.to_string_lossy().into_owned())
+    }
+
+    fn exe_name(&self) -> String {
+        Self::exe_name_of(&self.path).unwrap_or_default()
+    }
+
+    fn is_running(&self) -> bool {
+        let name = self.exe_name();
+        process_snapshot().iter().any(|process| process.name.eq_ignore_ascii_case(&name))
+    }
+
+    /// Starts the executable from its own folder, as a shortcut would. The
+    /// child is not waited for and keeps running when the worker stops.
+    fn spawn(&self) -> io::Result<()> {
+        let mut cmd = Command::new(&self.path);
+        cmd.args(&self.args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            cmd.current_dir(dir);
+        }
+        cmd.spawn().map(drop)
+    }
+}
+
+/// `--jitter-ms`: spreads each pause evenly over `base ± spread`, from a
+/// xorshift generator seeded per loop from the standard library's random
+/// hasher keys. Nothing here needs more than "not periodic".
+struct Jitter {
+    spread_ms: u64,
+    state: u64,
+}
+
+impl Jitter {
+    fn new(spread_ms: u64) -> Self {
+        let seed = std::collections::hash_map::RandomState::new().build_hasher().finish();
+        Self {
+            spread_ms,
+            // Xorshift never leaves zero.
+            state: seed | 1,
+        }
+    }
+
+    fn apply(&mut self, base: Duration) -> Duration {
+        if self.spread_ms == 0 {
+            return base;
+        }
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        let offset = self.state % (2 * self.spread_ms + 1);
+        let millis = (base.as_millis() as u64 + offset).saturating_sub(self.spread_ms);
+        Duration::from_millis(millis.max(MIN_INTERVAL_MS))
+    }
+}
+
+/// Sliding one-minute window of foreground changes for
+/// `--max-steals-per-min`.
+struct StealBudget {
+    limit: Option<u32>,
+    recent: VecDeque<Instant>,
+}
+
+impl StealBudget {
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    fn new(limit: Option<u32>) -> Self {
+        Self {
+            limit,
+            recent: VecDeque::new(),
+        }
+    }
+
+    fn available(&mut self) -> bool {
+        while self
+            .recent
+            .front()
+            .is_some_and(|stolen| stolen.elapsed() >= Self::WINDOW)
+        {
+            self.recent.pop_front();
+        }
+        self.limit.is_none_or(|limit| self.recent.len() < limit as usize)
+    }
+
+    fn record(&mut self) {
+        if self.limit.is_some() {
+            self.recent.push_back(Instant::now());
+        }
+    }
+}
+
+/// Resolves each rule on its own, in pair/exe/path/title order, instead of
+/// stopping at the first hit. A window claimed by an earlier rule is left
+/// out of later ones so it only gets one slice per round.
+fn rule_windows(config: &ResolvedConfig) -> Vec<Option<HWND>> {
+    let scan = TargetScan::default();
+    let mut windows: Vec<Option<HWND>> =
+        config.hwnd_files.iter().map(|path| read_hwnd_file(path, &config.options)).collect();
+    for pair in &config.title_exe_pairs {
+        let pids = scan.pids_named(&pair.exe);
+        let found = scan.windows().find(&pids, &config.options, Some(&pair.title));
+        windows.push(found.map(|(hwnd, _)| hwnd));
+    }
+    for rule in &config.class_matches {
+        windows.push(find_window_by_class(&scan, rule, &config.options));
+    }
+    for &pid in &config.pids {
+        windows.push(find_process_window(&scan, pid, &config.options));
+    }
+    for path in &config.exe_paths {
+        let selected = select_process_windows(&scan, scan.pids_by_path(path), &config.options);
+        windows.push(selected.first().map(|&(hwnd, _)| hwnd));
+    }
+    for name in &config.process_names {
+        let selected = select_process_windows(&scan, scan.pids_named(name), &config.options);
+        windows.push(selected.first().map(|&(hwnd, _)| hwnd));
+    }
+    for fragment in &config.path_fragments {
+        let pids = scan.pids_by_path_fragment(fragment);
+        let selected = select_process_windows(&scan, pids, &config.options);
+        windows.push(selected.first().map(|&(hwnd, _)| hwnd));
+    }
+    for pattern in &config.title_regexes {
+        windows.push(find_window_by_regex(&scan, pattern, &config.options));
+    }
+    for entry in &config.window_titles {
+        windows.push(
+            title_candidates(entry, config.options.title_alternatives)
+                .iter()
+                .find_map(|title| find_window_by_title(&scan, title, &config.options)),
+        );
+    }
+    for index in 1..windows.len() {
+        if windows[index].is_some() && windows[..index].contains(&windows[index]) {
+            windows[index] = None;
+        }
+    }
+    windows
+}
+
+const LATENCY_REPORT_EVERY: u64 = 600;
+const JIGGLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Injects a one-pixel relative move and its reverse, which resets pointer
+/// idle detection, then puts the cursor back exactly where it was in case
+/// pointer acceleration made the two moves unequal.
+fn jiggle_cursor() {
+    let step = |dx: i32| INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                dx,
+                dwFlags: MOUSEEVENTF_MOVE,
+                ..Default::default()
+            },
+        },
+    };
+    unsafe {
+        let mut original = POINT::default();
+        let restore = GetCursorPos(&mut original).is_ok();
+        SendInput(&[step(1), step(-1)], std::mem::size_of::<INPUT>() as i32);
+        if restore {
+            let _ = SetCursorPos(original.x, original.y);
+        }
+    }
+}
+
+/// How recently the user must have left the mouse alone for `--jiggle` to
+/// nudge it.
+const JIGGLE_USER_QUIET: Duration = Duration::from_secs(1);
+
+/// The `--jiggle` worker: nudges the pointer every interval, but no more
+/// than once a second, and skips while the user is moving the mouse.
+fn jiggle_loop(active: &AtomicBool, config: &ResolvedConfig, verbose: bool) {
+    let mut iteration: u64 = 0;
+    let mut jitter = Jitter::new(config.options.jitter_ms);
+    let mut pointer = PointerWatch::default();
+    let started = Instant::now();
+    while active.load(Ordering::SeqCst) && !config.options.expired(started) {
+        iteration += 1;
+        let wait = jitter.apply(config.options.interval().max(JIGGLE_INTERVAL));
+        if config.options.pause_when_locked && is_workstation_locked() {
+            if verbose {
+                println!("[{}] workstation locked, skipping", iteration);
+            }
+        } else if let Some(wait) = config.options.schedule.and_then(|schedule| schedule.wait()) {
+            sleep_while_active(active, wait);
+            continue;
+        } else if pointer.recently_moved() {
+            if verbose {
+                println!("[{}] mouse moved within the last second, skipping", iteration);
+            }
+        } else if config.options.dry_run {
+            if verbose {
+                println!("[{}] would jiggle the pointer", iteration);
+            }
+        } else {
+            jiggle_cursor();
+            if verbose {
+                println!("[{}] jiggled the pointer", iteration);
+            }
+        }
+        pointer.sleep(active, wait);
+    }
+}
+
+/// Samples the pointer between `--jiggle` ticks to see whether the user is
+/// moving the mouse. A jiggle puts the pointer back where it was before the
+/// next sample, so the worker's own moves never count.
+#[derive(Default)]
+struct PointerWatch {
+    last: Option<POINT>,
+    moved_at: Option<Instant>,
+}
+
+impl PointerWatch {
+    fn sample(&mut self) {
+        let mut point = POINT::default();
+        let current = unsafe { GetCursorPos(&mut point) }.is_ok().then_some(point);
+        if self.last.is_some() && current.is_some() && current != self.last {
+            self.moved_at = Some(Instant::now());
+        }
+        self.last = current.or(self.last);
+    }
+
+    fn recently_moved(&self) -> bool {
+        self.moved_at.is_some_and(|at| at.elapsed() < JIGGLE_USER_QUIET)
+    }
+
+    /// Like `sleep_while_active`, sampling the pointer after every slice.
+    fn sleep(&mut self, active: &AtomicBool, duration: Duration) {
+        let slice = Duration::from_millis(REFRESH_INTERVAL_MS);
+        let mut remaining = duration;
+        self.sample();
+        while !remaining.is_zero() && active.load(Ordering::SeqCst) {
+            let step = remaining.min(slice);
+            thread::sleep(step);
+            remaining -= step;
+            self.sample();
+        }
+    }
+}
+
+const KEEPALIVE_KEY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The call that delivered a `--keepalive-key` press.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum KeyPath {
+    PostMessage,
+    SendInput,
+    KeybdEvent,
+}
+
+impl KeyPath {
+    fn name(self) -> &'static str {
+        match self {
+            KeyPath::PostMessage => "PostMessageW",
+            KeyPath::SendInput => "SendInput",
+            KeyPath::KeybdEvent => "keybd_event",
+        }
+    }
+}
+
+/// What follows SendInput inserting `sent` of the key-down/key-up pair.
+/// None inserted means the call was refused outright, which keybd_event
+/// sometimes gets past; after the key-down alone the press is given up,
+/// since sending it again would repeat the key-down.
+fn after_send_input(sent: u32) -> Result<KeyPath, &'static str> {
+    match sent {
+        2 => Ok(KeyPath::SendInput),
+        0 => Ok(KeyPath::KeybdEvent),
+        _ => Err("cut short, SendInput inserted only the key-down"),
+    }
+}
+
+/// `--keepalive-key`: a key-down/key-up pair for `hwnd`. Posting reaches
+/// that window alone; SendInput types into whatever has focus, so it is only
+/// used while `hwnd` is the foreground window. Returns how the key was sent,
+/// or why it was not.
+fn press_keepalive_key(hwnd: HWND, vk: u32, method: KeyMethod) -> Result<KeyPath, &'static str> {
+    match method {
+        KeyMethod::Post => {
+            let scan = unsafe { MapVirtualKeyW(vk, MAPVK_VK_TO_VSC) };
+            // Repeat count 1 and the scan code; the key-up also sets the
+            // previous-state and transition bits.
+            let down = 1 | (scan << 16);
+            let up = down | (1 << 30) | (1 << 31);
+            let key = WPARAM(vk as usize);
+            let posted = unsafe {
+                PostMessageW(hwnd, WM_KEYDOWN, key, LPARAM(down as isize)).is_ok()
+                    && PostMessageW(hwnd, WM_KEYUP, key, LPARAM(up as isize)).is_ok()
+            };
+            posted.then_some(KeyPath::PostMessage).ok_or("could not be posted")
+        }
+        KeyMethod::Input => {
+            if unsafe { GetForegroundWindow() } != hwnd {
+                return Err("skipped, target does not have focus");
+            }
+            let key = |flags| INPUT {
+                r#type: INPUT_KEYBOARD,
+                Anonymous: INPUT_0 {
+                    ki: KEYBDINPUT {
+                        wVk: VIRTUAL_KEY(vk as u16),
+                        dwFlags: flags,
+                        ..Default::default()
+                    },
+                },
+            };
+            let inputs = [key(KEYBD_EVENT_FLAGS(0)), key(KEYEVENTF_KEYUP)];
+            let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+            let path = after_send_input(sent)?;
+            if path == KeyPath::KeybdEvent {
+                // Virtual-key codes are validated to fit in a byte.
+                unsafe {
+                    keybd_event(vk as u8, 0, KEYBD_EVENT_FLAGS(0), 0);
+                    keybd_event(vk as u8, 0, KEYEVENTF_KEYUP, 0);
+                }
+            }
+            Ok(path)
+        }
+    }
+}
+
+/// How long the target's message loop took to handle WM_ACTIVATE, in fixed
+/// buckets so recording a sample is a single increment.
+#[derive(Default)]
+struct LatencyHistogram {
+    counts: [u64; LatencyHistogram::BOUNDS_MS.len() + 1],
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    const BOUNDS_MS: [u64; 5] = [1, 5, 20, 100, 500];
+
+    fn add(&mut self, latency: Duration) {
+        let bucket = Self::BOUNDS_MS
+            .iter()
+            .position(|&bound| latency < Duration::from_millis(bound))
+            .unwrap_or(Self::BOUNDS_MS.len());
+        self.counts[bucket] += 1;
+        self.max = self.max.max(latency);
+    }
+
+    fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+impl fmt::Display for LatencyHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (bound, count) in Self::BOUNDS_MS.iter().zip(&self.counts) {
+            write!(f, "<{}ms {} | ", bound, count)?;
+        }
+        write!(
+            f,
+            ">={}ms {} | max {}ms",
+            Self::BOUNDS_MS[Self::BOUNDS_MS.len() - 1],
+            self.counts[Self::BOUNDS_MS.len()],
+            self.max.as_millis()
+        )
+    }
+}
+
+/// Smoothed WM_ACTIVATE latencies above `PACE_SLOW` stretch the
+/// `--adaptive-pacing` gap; ones below `PACE_FAST` shrink it. In between, the
+/// gap holds, so an app hovering near either bound does not flap.
+const PACE_SLOW: Duration = Duration::from_millis(100);
+const PACE_FAST: Duration = Duration::from_millis(20);
+
+/// `--adaptive-pacing` state. Latencies are smoothed so one slow reply (a GC
+/// pause, a dialog opening) does not move the gap; a consistently slow or
+/// hung target doubles it each iteration up to the maximum so messages do not
+/// pile up in its queue, and a recovered one halves it back to the minimum.
+struct Pacer {
+    min: Duration,
+    max: Duration,
+    interval: Duration,
+    smoothed: Duration,
+}
+
+impl Pacer {
+    fn new(min_ms: u64, max_ms: u64) -> Self {
+        let min = Duration::from_millis(min_ms);
+        Self {
+            min,
+            max: Duration::from_millis(max_ms),
+            interval: min,
+            smoothed: Duration::ZERO,
+        }
+    }
+
+    fn observe(&mut self, latency: Duration) {
+        self.smoothed = (self.smoothed * 3 + latency) / 4;
+    }
+
+    /// Moves the gap after an iteration that sent something, returning the
+    /// new gap if it changed.
+    fn adjust(&mut self) -> Option<Duration> {
+        let next = if self.smoothed > PACE_SLOW {
+            (self.interval * 2).min(self.max)
+        } else if self.smoothed < PACE_FAST {
+            (self.interval / 2).max(self.min)
+        } else {
+            self.interval
+        };
+        (next != self.interval).then(|| {
+            self.interval = next;
+            next
+        })
+    }
+}
+
+const ESCALATION_BACKOFF: Duration = Duration::from_secs(30);
+
+/// `--escalate` state. Each rung is used on every iteration until `attempts`
+/// of them end with the target still not in the foreground, then the next
+/// rung takes over; past the last one the worker backs off and starts over.
+/// Reaching the foreground resets the ladder.
+struct EscalationLadder {
+    attempts: u32,
+    failures: u32,
+    rung: usize,
+}
+
+enum LadderStep {
+    Holding,
+    Escalated(&'static str, bool),
+    BackOff,
+}
+
+impl EscalationLadder {
+    const RUNGS: [&'static str; 3] = ["message", "attach-foreground", "topmost-toggle"];
+
+    fn new(attempts: u32) -> Self {
+        Self {
+            attempts,
+            failures: 0,
+            rung: 0,
+        }
+    }
+
+    /// Call after the activation message was sent to `hwnd`.
+    fn step(&mut self, hwnd: HWND) -> LadderStep {
+        if unsafe { GetForegroundWindow() } == hwnd {
+            self.failures = 0;
+            self.rung = 0;
+            return LadderStep::Holding;
+        }
+        self.failures += 1;
+        if self.failures >= self.attempts {
+            self.failures = 0;
+            self.rung += 1;
+            if self.rung == Self::RUNGS.len() {
+                self.rung = 0;
+                return LadderStep::BackOff;
+            }
+        }
+        match self.rung {
+            0 => LadderStep::Holding,
+            rung => LadderStep::Escalated(Self::RUNGS[rung], Self::climb(rung, hwnd)),
+        }
+    }
+
+    /// Applies rung `rung` on top of the message that was already sent.
+    fn climb(rung: usize, hwnd: HWND) -> bool {
+        match rung {
+            0 => true,
+            1 => attach_and_foreground(hwnd),
+            _ => toggle_topmost_and_foreground(hwnd),
+        }
+    }
+}
+
+const AUTO_METHOD_RETRIES: u32 = 3;
+
+/// `--auto-method` state. The first time a window is seen, the ladder's
+/// rungs are tried in order, each checked against GetForegroundWindow, and
+/// the first that works is kept for that window. After `AUTO_METHOD_RETRIES`
+/// iterations in a row where the kept method leaves the window in the
+/// background, it is detected again. A window no rung helps is retried after
+/// `ESCALATION_BACKOFF`.
+#[derive(Default)]
+struct MethodDetector {
+    targets: Vec<DetectedMethod>,
+}
+
+struct DetectedMethod {
+    hwnd: HWND,
+    rung: Option<usize>,
+    failures: u32,
+    since: Instant,
+}
+
+enum MethodEvent {
+    Using,
+    Detected(&'static str),
+    NoneWorked,
+    Failing(&'static str),
+}
+
+impl MethodDetector {
+    /// Call after the activation message was sent to `hwnd`.
+    fn step(&mut self, hwnd: HWND) -> MethodEvent {
+        let Some(index) = self.targets.iter().position(|target| target.hwnd == hwnd) else {
+            return self.detect(hwnd);
+        };
+        let target = &mut self.targets[index];
+        let Some(rung) = target.rung else {
+            if target.since.elapsed() < ESCALATION_BACKOFF {
+                return MethodEvent::Using;
+            }
+            self.targets.remove(index);
+            return self.detect(hwnd);
+        };
+        EscalationLadder::climb(rung, hwnd);
+        if unsafe { GetForegroundWindow() } == hwnd {
+            target.failures = 0;
+            return MethodEvent::Using;
+        }
+        target.failures += 1;
+        if target.failures < AUTO_METHOD_RETRIES {
+            return MethodEvent::Using;
+        }
+        self.targets.remove(index);
+        MethodEvent::Failing(EscalationLadder::RUNGS[rung])
+    }
+
+    fn detect(&mut self, hwnd: HWND) -> MethodEvent {
+        let rung = (0..EscalationLadder::RUNGS.len()).find(|&rung| {
+            EscalationLadder::climb(rung, hwnd);
+            let foreground = unsafe { GetForegroundWindow() };
+            foreground == hwnd
+        });
+        self.targets.push(DetectedMethod {
+            hwnd,
+            rung,
+            failures: 0,
+            since: Instant::now(),
+        });
+        match rung {
+            Some(rung) => MethodEvent::Detected(EscalationLadder::RUNGS[rung]),
+            None => MethodEvent::NoneWorked,
+        }
+    }
+
+    /// Drops windows that are no longer targets, so a reopened app is
+    /// detected afresh.
+    fn forget_except(&mut self, live: &[HWND]) {
+        self.targets.retain(|target| live.contains(&target.hwnd));
+    }
+}
+
+/// Joins the foreground window's input queue so Windows treats the
+/// SetForegroundWindow call as coming from the active app.
+fn attach_and_foreground(hwnd: HWND) -> bool {
+    unsafe {
+        let foreground_thread = GetWindowThreadProcessId(GetForegroundWindow(), None);
+        let current_thread = GetCurrentThreadId();
+        let attached = foreground_thread != 0
+            && foreground_thread != current_thread
+            && AttachThreadInput(current_thread, foreground_thread, true).as_bool();
+        let granted = bring_to_foreground(hwnd);
+        if attached {
+            let _ = AttachThreadInput(current_thread, foreground_thread, false);
+        }
+        granted
+    }
+}
+
+/// `--activate-method foreground`: joins the target's own input queue for
+/// the duration of the call, so Windows treats BringWindowToTop and
+/// SetForegroundWindow as coming from the target's thread.
+fn attach_target_and_foreground(hwnd: HWND) -> bool {
+    unsafe {
+        let target_thread = GetWindowThreadProcessId(hwnd, None);
+        let current_thread = GetCurrentThreadId();
+        let attached = target_thread != 0
+            && target_thread != current_thread
+            && AttachThreadInput(current_thread, target_thread, true).as_bool();
+        let _ = BringWindowToTop(hwnd);
+        let granted = SetForegroundWindow(hwnd).as_bool();
+        if attached {
+            let _ = AttachThreadInput(current_thread, target_thread, false);
+        }
+        granted
+    }
+}
+
+/// Briefly makes the window topmost to lift it above everything, then
+/// drops the topmost bit again before asking for the foreground.
+fn toggle_topmost_and_foreground(hwnd: HWND) -> bool {
+    let flags = SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE;
+    unsafe {
+        let _ = SetWindowPos(hwnd, HWND_TOPMOST, 0, 0, 0, 0, flags);
+        let _ = SetWindowPos(hwnd, HWND_NOTOPMOST, 0, 0, 0, 0, flags);
+    }
+    attach_and_foreground(hwnd)
+}
+
+/// Restores a minimized window and asks for the foreground. Windows may
+/// refuse (it then flashes the taskbar button), which the return reports.
+fn bring_to_foreground(hwnd: HWND) -> bool {
+    unsafe {
+        if IsIconic(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+        SetForegroundWindow(hwnd).as_bool()
+    }
+}
+
+/// Whether the window's process, or one of its direct children (browsers
+/// play audio from a helper process), has an active session on the default
+/// playback device.
+fn window_is_playing(hwnd: HWND) -> bool {
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+    let mut pids: HashSet<u32> = process_snapshot()
+        .into_iter()
+        .filter(|process| process.parent_pid == pid)
+        .map(|process| process.pid)
+        .collect();
+    pids.insert(pid);
+    active_audio_pids().is_some_and(|playing| !playing.is_disjoint(&pids))
+}
+
+#[cfg(feature = "audio")]
+fn active_audio_pids() -> Option<HashSet<u32>> {
+    unsafe {
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).ok()?;
+        let device = enumerator.GetDefaultAudioEndpoint(eRender, eMultimedia).ok()?;
+        let manager: IAudioSessionManager2 = device.Activate(CLSCTX_ALL, None).ok()?;
+        let sessions = manager.GetSessionEnumerator().ok()?;
+        let mut pids = HashSet::new();
+        for index in 0..sessions.GetCount().ok()? {
+            let Ok(session) = sessions.GetSession(index) else {
+                continue;
+            };
+            if session.GetState().ok() != Some(AudioSessionStateActive) {
+                continue;
+            }
+            if let Ok(session) = session.cast::<IAudioSessionControl2>()
+                && let Ok(pid) = session.GetProcessId()
+            {
+                pids.insert(pid);
+            }
+        }
+        Some(pids)
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+fn active_audio_pids() -> Option<HashSet<u32>> {
+    None
+}
+
+/// Whether `--suppress-while-title` matches the window's title right now. The
+/// title is re-read every call so a state like "Updating..." clears on its own.
+fn title_suppressed(hwnd: HWND, options: &WorkerOptions) -> bool {
+    options
+        .suppress_while_title
+        .as_ref()
+        .is_some_and(|pattern| pattern.is_match(&window_title(hwnd)))
+}
+
+/// Time since the last keyboard/mouse input, if it is still inside
+/// `--input-grace`.
+fn within_input_grace(options: &WorkerOptions) -> Option<Duration> {
+    let grace = Duration::from_millis(options.input_grace_ms?);
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        ..Default::default()
+    };
+    if !unsafe { GetLastInputInfo(&mut info) }.as_bool() {
+        return None;
+    }
+    // Both are 32-bit tick counts, so wrapping subtraction survives the
+    // 49.7-day rollover.
+    let idle_ms = unsafe { GetTickCount() }.wrapping_sub(info.dwTime);
+    let idle = Duration::from_millis(u64::from(idle_ms));
+    (idle < grace).then_some(idle)
+}
+
+/// `--active-from`/`--active-to`, in minutes after local midnight. A window
+/// that ends before it starts runs past midnight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Schedule {
+    from: u32,
+    to: u32,
+}
+
+impl Schedule {
+    const DAY_SECS: u32 = 24 * 60 * 60;
+    /// Outside the window there is nothing to do but wait, so the loops
+    /// check far less often than every iteration.
+    const IDLE_POLL: Duration = Duration::from_secs(30);
+
+    /// `None` inside the window; otherwise how long to sleep before checking
+    /// again: until the window opens, but never more than `IDLE_POLL`, so a
+    /// clock change is noticed.
+    fn wait(&self) -> Option<Duration> {
+        let now = unsafe { GetLocalTime() };
+        let seconds = u32::from(now.wHour) * 3600 + u32::from(now.wMinute) * 60;
+        self.wait_at(seconds + u32::from(now.wSecond))
+    }
+
+    fn wait_at(&self, seconds: u32) -> Option<Duration> {
+        let (from, to) = (self.from * 60, self.to * 60);
+        let inside = if from <= to {
+            (from..to).contains(&seconds)
+        } else {
+            seconds >= from || seconds < to
+        };
+        if inside {
+            return None;
+        }
+        let until_open = (from + Self::DAY_SECS - seconds) % Self::DAY_SECS;
+        Some(Duration::from_secs(u64::from(until_open)).min(Self::IDLE_POLL))
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "active hours {}-{}", clock_time(self.from), clock_time(self.to))
+    }
+}
+
+fn clock_time(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Sends WM_ACTIVATE and returns the window's answer. With `--no-steal` the
+/// message is posted instead, after WM_NCACTIVATE and before WM_SETFOCUS when
+/// `--focus-messages` asks for the order a real activation delivers them in,
+/// and the result is 1 when every post was queued.
+fn send_activation(hwnd: HWND, options: &WorkerOptions) -> isize {
+    let mut wparam = WA_CLICKACTIVE;
+    // The high word of WM_ACTIVATE's wParam tells the window it is minimized,
+    // so it refreshes its activity state without restoring itself.
+    if options.keep_minimized && unsafe { IsIconic(hwnd) }.as_bool() {
+        wparam |= 1 << 16;
+    }
+    if !options.no_steal {
+        return unsafe { SendMessageW(hwnd, WM_ACTIVATE, WPARAM(wparam), LPARAM::default()) }.0;
+    }
+    let mut messages = vec![(WM_ACTIVATE, wparam)];
+    if options.focus_messages {
+        messages.insert(0, (WM_NCACTIVATE, 1));
+        messages.push((WM_SETFOCUS, 0));
+    }
+    let posted = messages.into_iter().all(|(message, wparam)| {
+        unsafe { PostMessageW(hwnd, message, WPARAM(wparam), LPARAM::default()) }.is_ok()
+    });
+    isize::from(posted)
+}
+
+/// Whether an activation reached `hwnd`: a sent message's reply carries no
+/// success flag, so only a failed post or a window that has closed count as
+/// failures.
+fn delivered(hwnd: HWND, granted: bool, result: isize, options: &WorkerOptions) -> bool {
+    granted || (!(options.no_steal && result == 0) && unsafe { IsWindow(hwnd) }.as_bool())
+}
+
+/// How `send_activation` delivers the activation, for log lines.
+fn activation_message(options: &WorkerOptions) -> &'static str {
+    match (options.no_steal, options.focus_messages) {
+        (false, _) => "WM_ACTIVATE",
+        (true, false) => "posted WM_ACTIVATE",
+        (true, true) => "posted WM_NCACTIVATE+WM_ACTIVATE+WM_SETFOCUS",
+    }
+}
+
+/// Sleeps for `duration` in refresh-sized slices so a cleared `active` flag
+/// is noticed promptly.
+fn sleep_while_active(active: &AtomicBool, duration: Duration) {
+    let slice = Duration::from_millis(REFRESH_INTERVAL_MS);
+    let mut remaining = duration;
+    while !remaining.is_zero() && active.load(Ordering::SeqCst) {
+        let step = remaining.min(slice);
+        thread::sleep(step);
+        remaining -= step;
+    }
+}
+
+/// Resolves every window the worker should activate this iteration.
+pub fn resolve_targets(config: &ResolvedConfig) -> Vec<HWND> {
+    if config.options.all_windows {
+        find_all_target_windows(config)
+    } else {
+        find_target_windows(config)
+    }
+}
+
+/// The first rule that matches, in rule order. Only `--exe-select all` can
+/// produce more than one window here.
+pub fn find_target_windows(config: &ResolvedConfig) -> Vec<HWND> {
+    let scan = TargetScan::default();
+    // While the primary has a window, its worker targets nothing else and
+    // every other worker stands down.
+    if let Some(primary) = &config.options.primary {
+        let windows = select_process_windows(&scan, scan.pids_named(primary), &config.options);
+        if !windows.is_empty() {
+            let owns_primary =
+                config.process_names.iter().any(|name| name.eq_ignore_ascii_case(primary));
+            return if owns_primary {
+                windows.into_iter().map(|(hwnd, _)| hwnd).collect()
+            } else {
+                Vec::new()
+            };
+        }
+    }
+    // Handle files, pairs and classes are strict rules, so a config that has
+    // any never falls back to the looser exe/title rules (including the
+    // default title).
+    if !config.hwnd_files.is_empty()
+        || !config.title_exe_pairs.is_empty()
+        || !config.class_matches.is_empty()
+    {
+        return config
+            .hwnd_files
+            .iter()
+            .find_map(|path| read_hwnd_file(path, &config.options))
+            .or_else(|| {
+                config.title_exe_pairs.iter().find_map(|pair| {
+                    let pids = scan.pids_named(&pair.exe);
+                    let found = scan.windows().find(&pids, &config.options, Some(&pair.title));
+                    found.map(|(hwnd, _)| hwnd)
+                })
+            })
+            .or_else(|| {
+                config
+                    .class_matches
+                    .iter()
+                    .find_map(|rule| find_window_by_class(&scan, rule, &config.options))
+            })
+            .into_iter()
+            .collect();
+    }
+    // A PID that has exited simply finds no window.
+    for &pid in &config.pids {
+        if let Some(hwnd) = find_process_window(&scan, pid, &config.options) {
+            return vec![hwnd];
+        }
+    }
+    for path in &config.exe_paths {
+        let windows = select_process_windows(&scan, scan.pids_by_path(path), &config.options);
+        if !windows.is_empty() {
+            return windows.into_iter().map(|(hwnd, _)| hwnd).collect();
+        }
+    }
+    for process_name in &config.process_names {
+        let pids = scan.pids_named(process_name);
+        let windows = select_process_windows(&scan, pids, &config.options);
+        if !windows.is_empty() {
+            return windows.into_iter().map(|(hwnd, _)| hwnd).collect();
+        }
+    }
+    for fragment in &config.path_fragments {
+        let pids = scan.pids_by_path_fragment(fragment);
+        let windows = select_process_windows(&scan, pids, &config.options);
+        if !windows.is_empty() {
+            return windows.into_iter().map(|(hwnd, _)| hwnd).collect();
+        }
+    }
+    for pattern in &config.title_regexes {
+        if let Some(hwnd) = find_window_by_regex(&scan, pattern, &config.options) {
+            return vec![hwnd];
+        }
+    }
+    for entry in &config.window_titles {
+        for title in title_candidates(entry, config.options.title_alternatives) {
+            if let Some(hwnd) = find_window_by_title(&scan, &title, &config.options) {
+                return vec![hwnd];
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// `--all`: every window any rule matches, in rule order, each once. Process
+/// rules take every matching process (as with `--exe-select all`) and title
+/// rules every window with a matching title; handle files and pairs still
+/// name one window each.
+pub fn find_all_target_windows(config: &ResolvedConfig) -> Vec<HWND> {
+    let scan = TargetScan::default();
+    let options = WorkerOptions { exe_select: ExeSelect::All, ..config.options.clone() };
+    let mut windows: Vec<HWND> =
+        config.hwnd_files.iter().filter_map(|path| read_hwnd_file(path, &options)).collect();
+    for pair in &config.title_exe_pairs {
+        let pids = scan.pids_named(&pair.exe);
+        let found = scan.windows().find(&pids, &options, Some(&pair.title));
+        windows.extend(found.map(|(hwnd, _)| hwnd));
+    }
+    for rule in &config.class_matches {
+        windows.extend(class_windows(&scan, rule, &options));
+    }
+    windows.extend(config.pids.iter().filter_map(|&pid| find_process_window(&scan, pid, &options)));
+    let mut process_pids = Vec::new();
+    for path in &config.exe_paths {
+        process_pids.extend(scan.pids_by_path(path));
+    }
+    for process_name in &config.process_names {
+        process_pids.extend(scan.pids_named(process_name));
+    }
+    for fragment in &config.path_fragments {
+        process_pids.extend(scan.pids_by_path_fragment(fragment));
+    }
+    let process_windows = select_process_windows(&scan, process_pids, &options);
+    windows.extend(process_windows.into_iter().map(|(hwnd, _)| hwnd));
+    for pattern in &config.title_regexes {
+        windows.extend(titled_windows(&scan, &options, |title| pattern.is_match(title)));
+    }
+    // Alternatives stay alternatives: the first one with any window wins.
+    for entry in &config.window_titles {
+        let candidates = title_candidates(entry, options.title_alternatives);
+        let matched = candidates.iter().find_map(|candidate| {
+            let wanted = candidate.to_lowercase();
+            let found: Vec<HWND> = titled_windows(&scan, &options, move |title| {
+                match options.match_mode {
+                    MatchMode::Exact => title.to_lowercase() == wanted,
+                    MatchMode::Contains => title.to_lowercase().contains(&wanted),
+                }
+            })
+            .collect();
+            (!found.is_empty()).then_some(found)
+        });
+        windows.extend(matched.into_iter().flatten());
+    }
+    let mut seen = HashSet::new();
+    windows.retain(|hwnd| seen.insert(hwnd.0 as usize));
+    windows
+}
+
+/// Applies `--exe-select` to the processes matching one rule, returning the
+/// chosen windows with their process IDs.
+fn select_process_windows(
+    scan: &TargetScan,
+    mut pids: Vec<u32>,
+    options: &WorkerOptions,
+) -> Vec<(HWND, u32)> {
+    if options.exe_select == ExeSelect::Newest {
+        // Processes whose creation time cannot be read sort last.
+        pids.sort_by_key(|&pid| std::cmp::Reverse(process_creation_time(pid)));
+    }
+    let mut windows = pids
+        .into_iter()
+        .filter_map(|pid| find_process_window(scan, pid, options).map(|hwnd| (hwnd, pid)));
+    match options.exe_select {
+        ExeSelect::First | ExeSelect::Newest => windows.next().into_iter().collect(),
+        ExeSelect::All => windows.collect(),
+        ExeSelect::LargestWindow => windows
+            .max_by_key(|&(hwnd, _)| window_area(hwnd))
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn process_creation_time(pid: u32) -> Option<u64> {
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut created = FILETIME::default();
+        let (mut exited, mut kernel, mut user) =
+            (FILETIME::default(), FILETIME::default(), FILETIME::default());
+        let result = GetProcessTimes(process, &mut created, &mut exited, &mut kernel, &mut user);
+        let _ = CloseHandle(process);
+        result.ok()?;
+        Some((u64::from(created.dwHighDateTime) << 32) | u64::from(created.dwLowDateTime))
+    }
+}
+
+fn window_size(hwnd: HWND) -> Option<(i32, i32)> {
+    let mut rect = RECT::default();
+    unsafe { GetWindowRect(hwnd, &mut rect) }.ok()?;
+    Some((rect.right - rect.left, rect.bottom - rect.top))
+}
+
+/// `--min-window-size`: both sides at least the minimum. A window whose
+/// rectangle cannot be read never qualifies.
+fn large_enough(size: Option<(i32, i32)>, (min_width, min_height): (i32, i32)) -> bool {
+    size.is_some_and(|(width, height)| width >= min_width && height >= min_height)
+}
+
+fn window_area(hwnd: HWND) -> i64 {
+    window_size(hwnd).map_or(0, |(width, height)| i64::from(width) * i64::from(height))
+}
+
+/// Reads a window handle written by another tool, accepting decimal or `0x`
+/// hex. A missing file, unparsable contents or a handle that no longer names
+/// a window all yield `None`, so the worker idles until the file is fixed.
+fn read_hwnd_file(path: &Path, options: &WorkerOptions) -> Option<HWND> {
+    let text = fs::read_to_string(path).ok()?;
+    let text = text.trim();
+    let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok()?,
+        None => text.parse::<usize>().ok()?,
+    };
+    let hwnd = HWND(value as *mut c_void);
+    let valid = value != 0 && unsafe { IsWindow(hwnd) }.as_bool();
+    (valid && !is_excluded_window(hwnd, options)).then_some(hwnd)
+}
+
+/// The process and window lists for one round of target resolution. Each is
+/// taken at most once, on first use, so however many exe, path and pair rules
+/// a config has they cost one process snapshot and one EnumWindows pass, and
+/// a config of exact titles alone costs neither.
+#[derive(Default)]
+struct TargetScan {
+    processes: OnceCell<Vec<ProcessEntry>>,
+    windows: OnceCell<WindowSnapshot>,
+}
+
+impl TargetScan {
+    fn processes(&self) -> &[ProcessEntry] {
+        self.processes.get_or_init(process_snapshot)
+    }
+
+    fn windows(&self) -> &WindowSnapshot {
+        self.windows.get_or_init(WindowSnapshot::take)
+    }
+
+    fn pids_named(&self, process_name: &str) -> Vec<u32> {
+        self.processes()
+            .iter()
+            .filter(|process| process.name.eq_ignore_ascii_case(process_name))
+            .map(|process| process.pid)
+            .collect()
+    }
+
+    /// PIDs whose full image path contains `fragment`, compared case-insensitively
+    /// with `/` treated as `\`. Processes we cannot open are skipped.
+    fn pids_by_path_fragment(&self, fragment: &str) -> Vec<u32> {
+        let needle = fragment.replace('/', "\\").to_lowercase();
+        self.processes()
+            .iter()
+            .map(|process| process.pid)
+            .filter(|&pid| {
+                process_image_path(pid)
+                    .is_some_and(|path| path.to_lowercase().contains(&needle))
+            })
+            .collect()
+    }
+
+    /// PIDs started from exactly `path` (already normalized), compared
+    /// case-insensitively. Only processes with the same file name are opened,
+    /// and those we may not open are skipped.
+    fn pids_by_path(&self, path: &str) -> Vec<u32> {
+        let file_name = path.rsplit('\\').next().unwrap_or(path);
+        let wanted = path.to_lowercase();
+        self.processes()
+            .iter()
+            .filter(|process| process.name.eq_ignore_ascii_case(file_name))
+            .map(|process| process.pid)
+            .filter(|&pid| {
+                process_image_path(pid).is_some_and(|image| image.to_lowercase() == wanted)
+            })
+            .collect()
+    }
+}
+
+/// `System.AppUserModel.ID`, which Store app shortcuts carry instead of a path.
+const PKEY_APP_USER_MODEL_ID: PROPERTYKEY = PROPERTYKEY {
+    fmtid: GUID::from_u128(0x9f4c2855_9f79_4b39_a8d0_e1d42de1d5f3),
+    pid: 5,
+};
+
+/// Turns a shortcut into an `--exe-path-contains` fragment: the full target
+/// path for desktop programs, or the package folder prefix under
+/// `WindowsApps` for Store apps addressed by AppUserModelID.
+fn resolve_shortcut(shortcut: &Path) -> Result<String> {
+    let failed = |reason: &str| anyhow!("shortcut {}: {}", shortcut.display(), reason);
+    unsafe {
+        let init = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        let resolved = (|| {
+            let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)
+                .map_err(|err| failed(&err.message()))?;
+            let wide = to_wide(&shortcut.to_string_lossy());
+            link.cast::<IPersistFile>()
+                .and_then(|file| file.Load(PCWSTR(wide.as_ptr()), STGM_READ))
+                .map_err(|err| failed(&format!("cannot be read ({})", err.message())))?;
+
+            let mut buffer = [0u16; 1024];
+            if link.GetPath(&mut buffer, std::ptr::null_mut(), 0).is_ok() {
+                let path = wide_ptr_to_string(&buffer);
+                if !path.is_empty() {
+                    if !Path::new(&path).exists() {
+                        return Err(failed(&format!("target {} does not exist", path)));
+                    }
+                    return Ok(path);
+                }
+            }
+
+            let aumid = link
+                .cast::<IPropertyStore>()
+                .and_then(|store| store.GetValue(&PKEY_APP_USER_MODEL_ID))
+                .ok()
+                .and_then(|value| BSTR::try_from(&value).ok())
+                .map(|value| value.to_string())
+                .unwrap_or_default();
+            // "Publisher.App_hash!AppId" installs to "WindowsApps\Publisher.App_<version>...".
+            match aumid.split_once('!').and_then(|(family, _)| family.rsplit_once('_')) {
+                Some((name, _)) if !name.is_empty() => Ok(format!("WindowsApps\\{}_", name)),
+                _ => Err(failed("points to neither a program nor a Store app")),
+            }
+        })();
+        if init.is_ok() {
+            CoUninitialize();
+        }
+        resolved
+    }
+}
+
+fn process_image_path(pid: u32) -> Option<String> {
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(process);
+        result.ok()?;
+        Some(String::from_utf16_lossy(&buffer[..len as usize]))
+    }
+}
+
+/// Resolves a process to its window. Console programs own no window of their
+/// own: the console is drawn by a `conhost.exe` (or `OpenConsole.exe`) child
+/// process, so when the process itself has no match we look at the window of
+/// its console host instead.
+fn find_process_window(scan: &TargetScan, pid: u32, options: &WorkerOptions) -> Option<HWND> {
+    let own = |pid| scan.windows().find(&[pid], options, None).map(|(hwnd, _)| hwnd);
+    own(pid).or_else(|| console_hosts(scan.processes(), pid).find_map(own))
+}
+
+/// The conhost/OpenConsole processes started for `pid`'s console, whose
+/// window stands in for a console program's.
+fn console_hosts(processes: &[ProcessEntry], pid: u32) -> impl Iterator<Item = u32> + '_ {
+    processes
+        .iter()
+        .filter(move |process| {
+            process.parent_pid == pid
+                && CONSOLE_HOSTS.iter().any(|host| process.name.eq_ignore_ascii_case(host))
+        })
+        .map(|process| process.pid)
+}
+
+/// The window-side half of a process rule: still alive, visible, titled,
+/// passing the style and size filters, not part of the shell, and with
+/// `title` (lowercased) in its title if given.
+fn title_rule_matches(hwnd: HWND, options: &WorkerOptions, title: Option<&str>) -> bool {
+    let visible = unsafe {
+        IsWindow(hwnd).as_bool()
+            && IsWindowVisible(hwnd).as_bool()
+            && GetWindowTextLengthW(hwnd) > 0
+    };
+    visible
+        && passes_style_filters(hwnd, &options.style_filters)
+        && options.min_window_size.is_none_or(|min| large_enough(window_size(hwnd), min))
+        && !is_excluded_window(hwnd, options)
+        && title.is_none_or(|needle| window_title(hwnd).to_lowercase().contains(needle))
+}
+
+/// Top-level windows and their process IDs from one EnumWindows pass, in
+/// z-order. Exact titles are looked up with FindWindowW, which needs no walk;
+/// every other rule is matched against this snapshot (see `TargetScan`).
+/// Windows close while it is taken and used, so handles whose owner cannot
+/// be read are left out and every match is re-checked before it is returned.
+struct WindowSnapshot {
+    windows: Vec<(HWND, u32)>,
+}
+
+impl WindowSnapshot {
+    fn take() -> Self {
+        let windows = enumerate_window_handles()
+            .into_iter()
+            .filter_map(|hwnd| window_pid(hwnd).map(|pid| (hwnd, pid)))
+            .collect();
+        Self { windows }
+    }
+
+    /// The first window (last with `--z-order bottom`) of any of `pids` that
+    /// passes `title_rule_matches`, with its process ID.
+    fn find(
+        &self,
+        pids: &[u32],
+        options: &WorkerOptions,
+        title: Option<&str>,
+    ) -> Option<(HWND, u32)> {
+        let title = title.map(str::to_lowercase);
+        // The owner is read again last: a window that closed during the
+        // checks, or whose handle now names a new window, must not match.
+        pick_in_z_order(&self.windows, pids, options.z_order, |hwnd, pid| {
+            title_rule_matches(hwnd, options, title.as_deref()) && window_pid(hwnd) == Some(pid)
+        })
+    }
+}
+
+/// The topmost (or bottommost) window of any of `pids` that `accepts`.
+/// Windows are checked from that end only until one passes.
+fn pick_in_z_order(
+    windows: &[(HWND, u32)],
+    pids: &[u32],
+    z_order: ZOrder,
+    mut accepts: impl FnMut(HWND, u32) -> bool,
+) -> Option<(HWND, u32)> {
+    let mut matches =
+        windows.iter().copied().filter(|&(hwnd, pid)| pids.contains(&pid) && accepts(hwnd, pid));
+    match z_order {
+        ZOrder::Top => matches.next(),
+        ZOrder::Bottom => matches.next_back(),
+    }
+}
+
+/// The process owning `hwnd`, or `None` once the window is gone.
+fn window_pid(hwnd: HWND) -> Option<u32> {
+    let mut pid = 0;
+    let thread = unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+    (thread != 0).then_some(pid)
+}
+
+/// The taskbar and desktop windows must never be activated: doing so pulls
+/// the desktop over the user's apps. Explorer owns them, so they are reachable
+/// through both `-e explorer.exe` and title rules.
+fn is_shell_window(hwnd: HWND) -> bool {
+    if unsafe { GetShellWindow() } == hwnd {
+        return true;
+    }
+    is_shell_class(&window_class(hwnd))
+}
+
+fn is_shell_class(class: &str) -> bool {
+    SHELL_WINDOW_CLASSES
+        .iter()
+        .any(|shell| class.eq_ignore_ascii_case(shell))
+}
+
+/// The last gate every candidate passes, whichever rule found it: shell
+/// windows, then the default and `--exclude-class` classes, then `--exclude`
+/// titles.
+fn is_excluded_window(hwnd: HWND, options: &WorkerOptions) -> bool {
+    if is_shell_window(hwnd) {
+        return true;
+    }
+    let defaults = if options.default_excludes { DEFAULT_EXCLUDED_CLASSES } else { &[] };
+    let class = window_class(hwnd);
+    let class_excluded = defaults
+        .iter()
+        .copied()
+        .chain(options.exclude_classes.iter().map(String::as_str))
+        .any(|excluded| class.eq_ignore_ascii_case(excluded));
+    class_excluded
+        || (!options.exclude_titles.is_empty()
+            && title_excluded(&window_title(hwnd), &options.exclude_titles))
+}
+
+/// Whether `title` contains any of the lowercased `excluded` substrings.
+fn title_excluded(title: &str, excluded: &[String]) -> bool {
+    let title = title.to_lowercase();
+    excluded.iter().any(|text| title.contains(text.as_str()))
+}
+
+fn passes_style_filters(hwnd: HWND, filters: &[StyleFilter]) -> bool {
+    if filters.is_empty() {
+        return true;
+    }
+    // Window styles fit in 32 bits, so GetWindowLongW is enough on every architecture.
+    let (style, ex_style) = unsafe {
+        (
+            GetWindowLongW(hwnd, GWL_STYLE) as u32,
+            GetWindowLongW(hwnd, GWL_EXSTYLE) as u32,
+        )
+    };
+    filters.iter().all(|filter| filter.matches(style, ex_style))
+}
+
+struct WindowInfo {
+    hwnd: HWND,
+    pid: u32,
+    title: String,
+    class: String,
+    visible: bool,
+}
+
+fn enumerate_window_handles() -> Vec<HWND> {
+    unsafe extern "system" fn enum_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let handles = unsafe { &mut *(lparam.0 as *mut Vec<HWND>) };
+        handles.push(hwnd);
+        BOOL(1)
+    }
+
+    let mut handles: Vec<HWND> = Vec::new();
+    let param = LPARAM(&mut handles as *mut Vec<HWND> as isize);
+    unsafe {
+        let _ = EnumWindows(Some(enum_proc), param);
+    }
+    handles
+}
+
+/// Lists every top-level window in z-order, leaving out any that closed
+/// before they could be read.
+fn enumerate_windows() -> Vec<WindowInfo> {
+    enumerate_window_handles()
+        .into_iter()
+        .filter_map(|hwnd| {
+            let pid = window_pid(hwnd)?;
+            Some(WindowInfo {
+                hwnd,
+                pid,
+                title: window_title(hwnd),
+                class: window_class(hwnd),
+                visible: unsafe { IsWindowVisible(hwnd).as_bool() },
+            })
+        })
+        .collect()
+}
+
+/// Visible, titled top-level windows paired with their executable names.
+fn pickable_windows() -> Vec<(WindowInfo, String)> {
+    let names = process_names_by_pid();
+    enumerate_windows()
+        .into_iter()
+        .filter(|info| info.visible && !info.title.is_empty())
+        .map(|info| {
+            let exe = names.get(&info.pid).cloned().unwrap_or_default();
+            (info, exe)
+        })
+        .collect()
+}
+
+struct ProcessEntry {
+    pid: u32,
+    parent_pid: u32,
+    name: String,
+}
+
+fn process_snapshot() -> Vec<ProcessEntry> {
+    let mut processes = Vec::new();
+    let Ok(snapshot) = (unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) }) else {
+        return processes;
+    };
+    let mut entry = PROCESSENTRY32W {
+        dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+        ..Default::default()
+    };
+    unsafe {
+        if Process32FirstW(snapshot, &mut entry).is_ok() {
+            loop {
+                processes.push(ProcessEntry {
+                    pid: entry.th32ProcessID,
+                    parent_pid: entry.th32ParentProcessID,
+                    name: wide_ptr_to_string(&entry.szExeFile),
+                });
+                if Process32NextW(snapshot, &mut entry).is_err() {
+                    break;
+                }
+            }
+        }
+        let _ = CloseHandle(snapshot);
+    }
+    processes
+}
+
+fn process_names_by_pid() -> HashMap<u32, String> {
+    process_snapshot()
+        .into_iter()
+        .map(|process| (process.pid, process.name))
+        .collect()
+}
+
+/// Finds a `--window` title. Exact titles go straight to FindWindowW;
+/// `--match-mode contains` has to read every title, so it walks the scan's
+/// window snapshot and takes the first visible window, in z-order, whose
+/// non-empty title contains `title` ignoring case.
+fn find_window_by_title(scan: &TargetScan, title: &str, options: &WorkerOptions) -> Option<HWND> {
+    if options.match_mode == MatchMode::Contains {
+        let needle = title.to_lowercase();
+        return titled_windows(scan, options, |title| title.to_lowercase().contains(&needle))
+            .next();
+    }
+    let wide = to_wide(title);
+    match unsafe { FindWindowW(None, PCWSTR(wide.as_ptr())) } {
+        Ok(hwnd) if !hwnd.0.is_null() && !is_excluded_window(hwnd, options) => Some(hwnd),
+        _ => None,
+    }
+}
+
+/// The first visible window, in z-order, whose title matches `pattern`.
+/// Like `--match-mode contains`, this reads every title in the scan's window
+/// snapshot, so all regex rules share one enumeration.
+fn find_window_by_regex(
+    scan: &TargetScan,
+    pattern: &Regex,
+    options: &WorkerOptions,
+) -> Option<HWND> {
+    titled_windows(scan, options, |title| pattern.is_match(title)).next()
+}
+
+/// The first visible window, in z-order, that a `--class` rule matches.
+fn find_window_by_class(
+    scan: &TargetScan,
+    rule: &ClassMatch,
+    options: &WorkerOptions,
+) -> Option<HWND> {
+    class_windows(scan, rule, options).next()
+}
+
+/// Visible windows from the scan's snapshot, in z-order, of the rule's class
+/// and, if it names one, with its title substring, minus excluded ones. Only
+/// the title filter needs a window to have a title.
+fn class_windows<'a>(
+    scan: &'a TargetScan,
+    rule: &'a ClassMatch,
+    options: &'a WorkerOptions,
+) -> impl Iterator<Item = HWND> + 'a {
+    let needle = rule.title.as_deref().map(str::to_lowercase);
+    scan.windows().windows.iter().map(|&(hwnd, _)| hwnd).filter(move |&hwnd| {
+        let visible = unsafe { IsWindow(hwnd).as_bool() && IsWindowVisible(hwnd).as_bool() };
+        visible
+            && window_class(hwnd).eq_ignore_ascii_case(&rule.class)
+            && needle
+                .as_ref()
+                .is_none_or(|needle| window_title(hwnd).to_lowercase().contains(needle.as_str()))
+            && !is_excluded_window(hwnd, options)
+    })
+}
+
+/// Visible windows from the scan's snapshot, in z-order, whose non-empty
+/// title passes `matches`, minus excluded ones.
+fn titled_windows<'a>(
+    scan: &'a TargetScan,
+    options: &'a WorkerOptions,
+    matches: impl Fn(&str) -> bool + 'a,
+) -> impl Iterator<Item = HWND> + 'a {
+    scan.windows().windows.iter().map(|&(hwnd, _)| hwnd).filter(move |&hwnd| {
+        let visible = unsafe { IsWindow(hwnd).as_bool() && IsWindowVisible(hwnd).as_bool() };
+        visible && {
+            let title = window_title(hwnd);
+            !title.is_empty() && matches(&title)
+        } && !is_excluded_window(hwnd, options)
+    })
+}
+
+/// Reads the full title however long it is. The buffer is sized from
+/// GetWindowTextLengthW plus one spare slot, so a copy that fills it means the
+/// title grew in between and is retried with more room instead of truncated.
+fn window_title(hwnd: HWND) -> String {
+    unsafe {
+        let len = GetWindowTextLengthW(hwnd);
+        if len <= 0 {
+            return String::new();
+        }
+        let mut capacity = len as usize + 2;
+        loop {
+            let mut buffer = vec![0u16; capacity];
+            let copied = GetWindowTextW(hwnd, &mut buffer).max(0) as usize;
+            if copied + 1 < capacity || capacity >= MAX_TITLE_LEN {
+                return String::from_utf16_lossy(&buffer[..copied]);
+            }
+            capacity *= 2;
+        }
+    }
+}
+
+fn window_class(hwnd: HWND) -> String {
+    let mut buffer = [0u16; 256];
+    let len = unsafe { GetClassNameW(hwnd, &mut buffer) };
+    String::from_utf16_lossy(&buffer[..len.max(0) as usize])
+}
+
+fn to_wide(value: &str) -> Vec<u16> {
+    OsStr::new(value)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect()
+}
+
+fn wide_ptr_to_string(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..len])
+}
+
+/// Fails when there is no desktop a user could see: in session 0, or on a
+/// window station without `WSF_VISIBLE`, no target window can be activated.
+fn ensure_interactive_session() -> Result<()> {
+    let mut session = 0;
+    unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut session) }
+        .context("failed to query the session id")?;
+    if session == 0 {
+        return Err(anyhow!(
+            "KeepActive is running in session 0 (services), which has no interactive desktop; \
+             start it from a signed-in user's session instead"
+        ));
+    }
+    let mut flags = USEROBJECTFLAGS::default();
+    let visible = unsafe {
+        GetProcessWindowStation().and_then(|station| {
+            GetUserObjectInformationW(
+                HANDLE(station.0),
+                UOI_FLAGS,
+                Some(&mut flags as *mut USEROBJECTFLAGS as *mut c_void),
+                std::mem::size_of::<USEROBJECTFLAGS>() as u32,
+                None,
+            )
+        })
+    }
+    .map_or(true, |()| flags.dwFlags & WSF_VISIBLE as u32 != 0);
+    if !visible {
+        return Err(anyhow!(
+            "KeepActive is running on a non-interactive window station (session {}); \
+             windows there are invisible to the user, so start it from the desktop instead",
+            session
+        ));
+    }
+    Ok(())
+}
+
+/// The input desktop cannot be opened (or switched to) while the secure
+/// lock screen owns it, which is the cheapest lock check a worker can poll.
+fn is_workstation_locked() -> bool {
+    unsafe {
+        match OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_SWITCHDESKTOP) {
+            Ok(desktop) => {
+                let locked = SwitchDesktop(desktop).is_err();
+                let _ = CloseDesktop(desktop);
+                locked
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+/// `%LOCALAPPDATA%\KeepActive`, or a KeepActive folder under the temp
+/// directory when the variable is missing.
+fn app_data_dir() -> PathBuf {
+    env::var_os("LOCALAPPDATA")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join("KeepActive")
+}
+
+static CRASH_DUMP_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Registers an unhandled-exception filter that writes a minidump locally.
+/// Nothing is sent anywhere; the dump stays in the directory until deleted.
+fn install_crash_dump_handler() {
+    let dir = app_data_dir().join("CrashDumps");
+    if fs::create_dir_all(&dir).is_err() {
+        return;
+    }
+    let _ = CRASH_DUMP_DIR.set(dir);
+    unsafe { SetUnhandledExceptionFilter(Some(write_crash_dump)) };
+}
+
+unsafe extern "system" fn write_crash_dump(exception: *const EXCEPTION_POINTERS) -> i32 {
+    // EXCEPTION_CONTINUE_SEARCH: let Windows Error Reporting run afterwards.
+    const CONTINUE_SEARCH: i32 = 0;
+    let Some(dir) = CRASH_DUMP_DIR.get() else {
+        return CONTINUE_SEARCH;
+    };
+    let now = unsafe { GetSystemTime() };
+    let path = dir.join(format!(
+        "KeepActive-{:04}{:02}{:02}-{:02}{:02}{:02}-{}.dmp",
+        now.wYear,
+        now.wMonth,
+        now.wDay,
+        now.wHour,
+        now.wMinute,
+        now.wSecond,
+        std::process::id()
+    ));
+    let Ok(file) = fs::File::create(&path) else {
+        return CONTINUE_SEARCH;
+    };
+    let info = MINIDUMP_EXCEPTION_INFORMATION {
+        ThreadId: unsafe { GetCurrentThreadId() },
+        ExceptionPointers: exception as *mut EXCEPTION_POINTERS,
+        ClientPointers: BOOL(0),
+    };
+    unsafe {
+        let _ = MiniDumpWriteDump(
+            GetCurrentProcess(),
+            GetCurrentProcessId(),
+            HANDLE(file.as_raw_handle()),
+            MiniDumpWithThreadInfo,
+            Some(&info),
+            None,
+            None,
+        );
+    }
+    CONTINUE_SEARCH
+}
+
+fn hide_console_window() {
+    unsafe {
+        let hwnd = GetConsoleWindow();
+        if !hwnd.0.is_null() {
+            let _ = ShowWindow(hwnd, SW_HIDE);
+        }
+    }
+}
+
+const RUN_KEY: PCWSTR = w!("Software\\Microsoft\\Windows\\CurrentVersion\\Run");
+const RUN_VALUE_NAME: PCWSTR = w!("KeepActive");
+
+/// Adds a `KeepActive` value to the current user's Run key that starts this
+/// executable with the same arguments, minus `--install-autostart`.
+fn install_autostart() -> Result<()> {
+    let exe = env::current_exe().context("failed to determine executable path")?;
+    let mut command = format!("\"{}\"", exe.display());
+    for arg in env::args().skip(1).filter(|arg| arg != "--install-autostart") {
+        command.push(' ');
+        command.push_str(&quote_argument(&arg));
+    }
+    let data = to_wide(&command);
+    let bytes = (data.len() * std::mem::size_of::<u16>()) as u32;
+    unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            RUN_KEY,
+            RUN_VALUE_NAME,
+            REG_SZ.0,
+            Some(data.as_ptr().cast()),
+            bytes,
+        )
+    }
+    .ok()
+    .context("failed to write the autostart entry")?;
+    println!("KeepActive will start at logon: {}", command);
+    Ok(())
+}
+
+/// Removes the `KeepActive` Run value and nothing else.
+fn uninstall_autostart() -> Result<()> {
+    let result = unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, RUN_KEY, RUN_VALUE_NAME) };
+    if result == ERROR_FILE_NOT_FOUND {
+        println!("KeepActive was not set to start at logon");
+        return Ok(());
+    }
+    result.ok().context("failed to remove the autostart entry")?;
+    println!("KeepActive will no longer start at logon");
+    Ok(())
+}
+
+const INSTANCE_MUTEX_NAME: PCWSTR = w!("Local\\KeepActive-instance");
+
+/// Held by the running GUI or CLI controller for its whole lifetime.
+struct InstanceMutex(HANDLE);
+
+impl InstanceMutex {
+    /// `None` if another controller in this session already holds it.
+    fn acquire() -> Option<Self> {
+        let handle = unsafe { CreateMutexW(None, false, INSTANCE_MUTEX_NAME) }.ok()?;
+        if unsafe { GetLastError() } == ERROR_ALREADY_EXISTS {
+            let _ = unsafe { CloseHandle(handle) };
+            return None;
+        }
+        Some(Self(handle))
+    }
+}
+
+impl Drop for InstanceMutex {
+    fn drop(&mut self) {
+        let _ = unsafe { CloseHandle(self.0) };
+    }
+}
+
+/// Checks for the mutex without creating it. An elevated instance's mutex
+/// cannot be opened from an unelevated process, but access denied still
+/// means it exists.
+fn another_instance_running() -> bool {
+    match unsafe { OpenMutexW(SYNCHRONIZATION_SYNCHRONIZE, false, INSTANCE_MUTEX_NAME) } {
+        Ok(handle) => {
+            let _ = unsafe { CloseHandle(handle) };
+            true
+        }
+        Err(err) => err.code() == E_ACCESSDENIED,
+    }
+}
+
+/// Brings the running GUI forward, even from the tray. A CLI instance has no
+/// window, so there is only the message.
+fn switch_to_running_instance() {
+    println!("KeepActive is already running");
+    if let Ok(hwnd) = unsafe { FindWindowW(None, w!("KeepActive")) }
+        && !hwnd.0.is_null()
+    {
+        unsafe {
+            let _ = ShowWindow(hwnd, SW_SHOWNORMAL);
+            let _ = SetForegroundWindow(hwnd);
+        }
+    }
+}
+
+/// ShellExecuteW's result when the user answers No at the UAC prompt.
+const SE_ERR_ACCESSDENIED: isize = 5;
+
+const ELEVATION_DECLINED: &str = "Administrator rights are required to keep other apps' \
+                                  windows active, and the UAC prompt was declined. Start \
+                                  KeepActive again and choose Yes to continue.";
+
+/// Relaunches elevated unless already elevated. Declining the UAC prompt
+/// is an answer, not a failure: the GUI says so in a message box and exits
+/// cleanly, while the CLI prints it and exits with code 1.
+fn ensure_admin(gui: bool) -> Result<()> {
+    if is_elevated()? {
+        return Ok(());
+    }
+    let code = relaunch_as_admin()?;
+    if code != SE_ERR_ACCESSDENIED {
+        return Err(anyhow!("failed to request elevation (ShellExecuteW error code {})", code));
+    }
+    if gui {
+        nwg::simple_message("KeepActive", ELEVATION_DECLINED);
+        std::process::exit(0);
+    }
+    eprintln!("{}", ELEVATION_DECLINED);
+    std::process::exit(1);
+}
+
+fn is_elevated() -> Result<bool> {
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token)
+            .context("OpenProcessToken failed")?;
+
+        let mut elevation = TOKEN_ELEVATION::default();
+        let mut size = std::mem::size_of::<TOKEN_ELEVATION>() as u32;
+        let info_result = GetTokenInformation(
+            token,
+            TokenElevation,
+            Some((&mut elevation as *mut TOKEN_ELEVATION).cast::<c_void>()),
+            size,
+            &mut size,
+        );
+        let _ = CloseHandle(token);
+
+        info_result.context("GetTokenInformation failed")?;
+        Ok(elevation.TokenIsElevated != 0)
+    }
+}
+
+/// Starts this executable again with the same arguments through the "runas"
+/// verb and exits. Only returns if ShellExecuteW failed, with its result.
+fn relaunch_as_admin() -> Result<isize> {
+    let exe = std::env::current_exe().context("failed to determine executable path")?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let quoted_args: Vec<String> = args.iter().map(|a| quote_argument(a)).collect();
+    let params = quoted_args.join(" ");
+
+    let exe_w = exe.as_os_str().encode_wide().chain(std::iter::once(0)).collect::<Vec<_>>();
+    let params_w = params.encode_utf16().chain(std::iter::once(0)).collect::<Vec<_>>();
+
+    let result = unsafe {
+        ShellExecuteW(
+            None,
+            w!("runas"),
+            PCWSTR(exe_w.as_ptr()),
+            if params.is_empty() {
+                PCWSTR::null()
+            } else {
+                PCWSTR(params_w.as_ptr())
+            },
+            PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+
+    if (result.0 as isize) <= 32 {
+        return Ok(result.0 as isize);
+    }
+
+    std::process::exit(0);
+}
+
+fn quote_argument(arg: &str) -> String {
+    if arg.is_empty() || arg.chars().any(|c| c == ' ' || c == '\t' || c == '"') {
+        let mut escaped = String::from("\"");
+        let mut backslashes = 0;
+        for ch in arg.chars() {
+            match ch {
+                '\\' => {
+                    backslashes += 1;
+                }
+                '"' => {
+                    escaped.push_str(&"\\".repeat(backslashes * 2 + 1));
+                    escaped.push('"');
+                    backslashes = 0;
+                }
+                _ => {
+                    if backslashes > 0 {
+                        escaped.push_str(&"\\".repeat(backslashes));
+                        backslashes = 0;
+                    }
+                    escaped.push(ch);
+                }
+            }
+        }
+        if backslashes > 0 {
+            escaped.push_str(&"\\".repeat(backslashes * 2));
+        }
+        escaped.push('"');
+        escaped
+    } else {
+        arg.to_string()
+    }
+}
+
+/// The titles a window target stands for. Under `--title-alternatives`,
+/// `First|Second` splits into candidates that a single worker tries in order
+/// so a renamed window is still picked up; otherwise the entry is one title,
+/// `|` and all.
+fn title_candidates(entry: &str, alternatives: bool) -> Vec<String> {
+    if alternatives {
+        normalize_list(entry.split('|').map(str::to_string).collect())
+    } else {
+        normalize_list(vec![entry.to_string()])
+    }
+}
+
+/// A `--hotkey` combination.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Hotkey {
+    Off,
+    Keys { modifiers: HOT_KEY_MODIFIERS, vk: u32 },
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Hotkey::Keys { modifiers, vk } = *self else {
+            return f.write_str("none");
+        };
+        for (modifier, name) in HOTKEY_MODIFIERS {
+            if modifiers.contains(*modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        f.write_str(&key_name(vk))
+    }
+}
+
+/// `F15`, `K` or `7` for the keys `key_code` knows, `0x..` for the rest.
+fn key_name(vk: u32) -> String {
+    match vk {
+        0x70..=0x87 => format!("F{}", vk - 0x6F),
+        0x30..=0x39 | 0x41..=0x5A => char::from_u32(vk).map_or_else(String::new, String::from),
+        _ => format!("0x{:02X}", vk),
+    }
+}
+
+/// The virtual-key code of a letter, digit or F1-F24, in any case.
+fn key_code(name: &str) -> Option<u32> {
+    match name.to_ascii_lowercase().as_bytes() {
+        [ch] if ch.is_ascii_alphanumeric() => Some(ch.to_ascii_uppercase() as u32),
+        [b'f', digits @ ..] => std::str::from_utf8(digits)
+            .ok()
+            .and_then(|digits| digits.parse::<u32>().ok())
+            .filter(|number| (1..=24).contains(number))
+            .map(|number| 0x6F + number),
+        _ => None,
+    }
+}
+
+/// Parses a `--keepalive-key`: a name `key_code` accepts, or any virtual-key
+/// code in `0x` hex or decimal.
+fn parse_virtual_key(value: &str) -> Result<u32, String> {
+    let value = value.trim();
+    let code = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => key_code(value).or_else(|| value.parse().ok()),
+    };
+    code.filter(|code| (1..=0xFE).contains(code))
+        .ok_or_else(|| format!("expected a key such as F15 or a virtual-key code, got '{}'", value))
+}
+
+const HOTKEY_MODIFIERS: &[(HOT_KEY_MODIFIERS, &str)] =
+    &[(MOD_CONTROL, "Ctrl"), (MOD_ALT, "Alt"), (MOD_SHIFT, "Shift"), (MOD_WIN, "Win")];
+
+/// Parses `Ctrl+Alt+K`-style combinations: any of Ctrl, Alt, Shift and Win
+/// plus one letter, digit or F1-F24, in any case. `none` turns the hotkey off.
+fn parse_hotkey(value: &str) -> Result<Hotkey, String> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Ok(Hotkey::Off);
+    }
+    let mut modifiers = HOT_KEY_MODIFIERS(0);
+    let mut key = None;
+    for part in value.split('+').map(str::trim) {
+        let lower = part.to_ascii_lowercase();
+        let modifier = match lower.as_str() {
+            "ctrl" | "control" => Some(MOD_CONTROL),
+            "alt" => Some(MOD_ALT),
+            "shift" => Some(MOD_SHIFT),
+            "win" => Some(MOD_WIN),
+            _ => None,
+        };
+        if let Some(modifier) = modifier {
+            modifiers |= modifier;
+            continue;
+        }
+        if key.is_some() {
+            return Err(format!("'{}' names more than one key", value));
+        }
+        let vk = key_code(part);
+        key = Some(vk.ok_or_else(|| format!("unknown key '{}' in '{}'", part, value))?);
+    }
+    let vk = key.ok_or_else(|| format!("'{}' has modifiers but no key", value))?;
+    if modifiers.0 == 0 {
+        return Err(format!("'{}' needs at least one of Ctrl, Alt, Shift or Win", value));
+    }
+    Ok(Hotkey::Keys { modifiers, vk })
+}
+
+/// Parses `WIDTHxHEIGHT` in pixels, e.g. `800x600`.
+fn parse_window_size(value: &str) -> Result<(i32, i32), String> {
+    let (width, height) = value
+        .trim()
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, e.g. 800x600, got '{}'", value))?;
+    let dimension = |part: &str| {
+        part.trim()
+            .parse::<i32>()
+            .ok()
+            .filter(|&pixels| pixels >= 0)
+            .ok_or_else(|| format!("invalid size '{}' in '{}'", part, value))
+    };
+    Ok((dimension(width)?, dimension(height)?))
+}
+
+/// Parses a 24-hour `HH:MM` local time into minutes after midnight.
+fn parse_clock_time(value: &str) -> Result<u32, String> {
+    let invalid = || format!("expected a 24-hour time such as 22:00, got '{}'", value);
+    let (hours, minutes) = value.trim().split_once(':').ok_or_else(invalid)?;
+    let hours = hours.parse::<u32>().ok().filter(|&hours| hours < 24).ok_or_else(invalid)?;
+    let minutes = Some(minutes)
+        .filter(|minutes| minutes.len() == 2)
+        .and_then(|minutes| minutes.parse::<u32>().ok())
+        .filter(|&minutes| minutes < 60)
+        .ok_or_else(invalid)?;
+    Ok(hours * 60 + minutes)
+}
+
+/// Parses `90`, `90s`, `30m`, `2h` or combinations such as `1h30m`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for ch in value.chars() {
+        if ch.is_ascii_digit() {
+            digits.push(ch);
+            continue;
+        }
+        let unit = match ch.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86_400,
+            _ => return Err(format!("invalid duration '{}': unknown unit '{}'", value, ch)),
+        };
+        let amount: u64 = digits
+            .parse()
+            .map_err(|_| format!("invalid duration '{}': expected a number before '{}'", value, ch))?;
+        total = total.saturating_add(amount.saturating_mul(unit));
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        // A trailing bare number counts as seconds.
+        total = total.saturating_add(digits.parse().unwrap_or(u64::MAX));
+    }
+    if total == 0 {
+        return Err(format!("invalid duration '{}' (examples: 90s, 30m, 2h, 1h30m)", value));
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// `--launch`: the worker for the executable's own `--exe` rule needs to
+/// exist, so it is added if missing.
+fn add_launch_target(process_names: &mut Vec<String>, launch: Option<&Path>) {
+    if let Some(name) = launch.and_then(LaunchTarget::exe_name_of)
+        && !process_names.iter().any(|existing| existing.eq_ignore_ascii_case(&name))
+    {
+        process_names.push(name);
+    }
+}
+
+/// The primary needs an exe rule (and so a worker) of its own.
+fn add_primary(process_names: &mut Vec<String>, primary: Option<&str>) {
+    if let Some(primary) = primary.map(str::trim)
+        && !process_names.iter().any(|name| name.eq_ignore_ascii_case(primary))
+    {
+        process_names.insert(0, primary.to_string());
+    }
+}
+
+fn display_list(values: &[String]) -> String {
+    if values.is_empty() {
+        "not set".to_string()
+    } else {
+        values.join(", ")
+    }
+}
+
+/// Makes an `--exe-path` comparable with QueryFullProcessImageNameW output:
+/// unquoted, absolute, with `..` resolved and `\` separators.
+fn normalize_exe_path(path: &str) -> String {
+    let path = path.trim().trim_matches('"');
+    std::path::absolute(path)
+        .map(|absolute| absolute.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.replace('/', "\\"))
+}
+
+fn dedup_pids(pids: &[u32]) -> Vec<u32> {
+    let mut seen = HashSet::new();
+    pids.iter().copied().filter(|pid| seen.insert(*pid)).collect()
+}
+
+/// Strips a trailing `@MS` interval from each entry, e.g. `Foo@500`, into
+/// `intervals`. Only digits count as an interval, so a title such as
+/// `Inbox - me@example.com` is kept whole.
+fn take_intervals(entries: Vec<String>, intervals: &mut HashMap<String, u64>) -> Vec<String> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let Some((rule, suffix)) = entry.rsplit_once('@') else {
+                return entry;
+            };
+            let interval_ms = Some(suffix)
+                .filter(|suffix| !suffix.is_empty() && suffix.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|suffix| suffix.parse::<u64>().ok());
+            match interval_ms {
+                Some(interval_ms) if !rule.trim().is_empty() => {
+                    intervals.insert(rule.trim().to_lowercase(), interval_ms);
+                    rule.to_string()
+                }
+                _ => entry,
+            }
+        })
+        .collect()
+}
+
+fn normalize_list(values: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    for value in values {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let owned = trimmed.to_string();
+        let key = owned.to_ascii_lowercase();
+        if seen.insert(key) {
+            result.push(owned);
+        }
+    }
+    result
+}
+
+/// Helpers shared by the test modules below.
+#[cfg(test)]
+mod test_support {
+    use super::*;
+
+    pub(super) fn config(args: &[&str]) -> AppConfig {
+        let argv = std::iter::once("KeepActive").chain(args.iter().copied());
+        AppConfig::from_args(&Args::try_parse_from(argv).expect("arguments should parse"))
+    }
+
+    pub(super) fn worker_args(cmd: &Command) -> Vec<String> {
+        cmd.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect()
+    }
+
+    /// The arguments a worker started with `options` receives.
+    pub(super) fn forwarded(options: &WorkerOptions) -> Vec<String> {
+        worker_args(&KeepAliveController::worker_command(Path::new("KeepActive.exe"), options))
+    }
+
+    /// `forwarded` parsed back the way the worker parses its command line.
+    pub(super) fn reparsed(options: &WorkerOptions, rules: &[&str]) -> Args {
+        let argv = std::iter::once("KeepActive".to_string())
+            .chain(forwarded(options))
+            .chain(rules.iter().map(|rule| rule.to_string()));
+        Args::try_parse_from(argv).expect("forwarded arguments should parse")
+    }
+}
+
+/// Pins the behaviour of the original `-w`/`-e` invocations so option
+/// refactors cannot change what `KeepActive -w "CounterSide"` does.
+#[cfg(test)]
+mod compat_tests {
+    use super::test_support::{config, forwarded, worker_args};
+    use super::*;
+
+    #[test]
+    fn no_rules_targets_the_default_title() {
+        let config = config(&[]).resolved();
+        assert_eq!(config.window_titles, [DEFAULT_WINDOW_TITLE]);
+        assert!(config.process_names.is_empty());
+        assert!(config.path_fragments.is_empty());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn short_and_long_flags_are_equivalent() {
+        let short = config(&["-w", "CounterSide", "-e", "notepad.exe"]).resolved();
+        let long = config(&["--window", "CounterSide", "--exe", "notepad.exe"]).resolved();
+        assert_eq!(short.window_titles, ["CounterSide"]);
+        assert_eq!(short.process_names, ["notepad.exe"]);
+        assert_eq!(short.window_titles, long.window_titles);
+        assert_eq!(short.process_names, long.process_names);
+    }
+
+    #[test]
+    fn exe_rules_keep_the_default_title_as_fallback() {
+        let config = config(&["-e", "notepad.exe"]).resolved();
+        assert_eq!(config.window_titles, [DEFAULT_WINDOW_TITLE]);
+        assert_eq!(config.process_names, ["notepad.exe"]);
+    }
+
+    #[test]
+    fn rules_are_trimmed_and_deduplicated_ignoring_case() {
+        let config = config(&["-w", " Foo ", "-w", "foo", "-w", " ", "-e", "A.EXE", "-e", "a.exe"])
+            .resolved();
+        assert_eq!(config.window_titles, ["Foo"]);
+        assert_eq!(config.process_names, ["A.EXE"]);
+    }
+
+    #[test]
+    fn gui_lists_resolve_like_flags() {
+        let options = config(&[]).options;
+        let config = ResolvedConfig::from_lists(Vec::new(), vec![" x.exe ".to_string()], options);
+        assert_eq!(config.window_titles, [DEFAULT_WINDOW_TITLE]);
+        assert_eq!(config.process_names, ["x.exe"]);
+    }
+
+    #[test]
+    fn pipes_in_titles_are_literal_by_default() {
+        let config = config(&["-w", "A | B"]).resolved();
+        assert_eq!(config.window_titles, ["A | B"]);
+        assert_eq!(title_candidates("A | B", config.options.title_alternatives), ["A | B"]);
+        let mut cmd = Command::new("KeepActive.exe");
+        KeepAliveController::push_fallback_titles(&mut cmd, &config.window_titles, &config.options);
+        assert_eq!(worker_args(&cmd), ["--window", "A | B"]);
+    }
+
+    #[test]
+    fn default_options_forward_nothing_to_workers() {
+        let options = config(&["-w", "CounterSide"]).options;
+        assert_eq!(forwarded(&options), ["--worker"]);
+    }
+}
+
+#[cfg(test)]
+mod session_summary_tests {
+    use super::test_support::config;
+    use super::*;
+
+    fn summary() -> SessionSummary {
+        let workers = vec![
+            ("window: Foo".to_string(), Some(ActivationCounts { sent: 100, failed: 0 })),
+            ("exe: game.exe".to_string(), Some(ActivationCounts { sent: 20, failed: 3 })),
+            ("mirror: a.exe=>\"B\"".to_string(), None),
+        ];
+        SessionSummary::new("quit", Duration::from_secs(3725), workers)
+    }
+
+    #[test]
+    fn status_lines_carry_the_counts() {
+        let (found, counts) = parse_status_line("found\t12\t1\tCounter\tSide");
+        assert_eq!(found.as_deref(), Some("Counter\tSide"));
+        assert_eq!(counts, ActivationCounts { sent: 12, failed: 1 });
+        assert_eq!(
+            parse_status_line("searching\t4\t0"),
+            (None, ActivationCounts { sent: 4, failed: 0 })
+        );
+        assert_eq!(parse_status_line("searching"), (None, ActivationCounts::default()));
+    }
+
+    #[test]
+    fn text_summary_totals_and_lists_every_worker() {
+        let text = summary().text();
+        assert!(text.contains("  Exit reason: quit"));
+        assert!(text.contains("  Uptime: 01:02:05"));
+        assert!(text.contains("  Activations: 120 sent, 3 failed"));
+        assert!(text.contains("    exe: game.exe (20 sent, 3 failed)"));
+        assert!(text.ends_with("    mirror: a.exe=>\"B\""));
+    }
+
+    #[test]
+    fn brief_summary_fits_the_status_line() {
+        assert_eq!(summary().brief(), "01:02:05, 120 sent, 3 failed");
+    }
+
+    #[test]
+    fn json_summary_is_one_object() {
+        assert_eq!(
+            summary().json(),
+            concat!(
+                r#"{"exit_reason":"quit","uptime_secs":3725,"activations":120,"failures":3,"#,
+                r#""workers":[{"worker":"window: Foo","activations":100,"failures":0},"#,
+                r#"{"worker":"exe: game.exe","activations":20,"failures":3},"#,
+                r#"{"worker":"mirror: a.exe=>\"B\"","activations":null,"failures":null}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn quiet_and_json_are_flags() {
+        assert!(config(&["--quiet"]).quiet);
+        assert!(config(&["--json"]).json);
+        let argv = ["KeepActive", "--quiet", "--json"];
+        assert!(Args::try_parse_from(argv).is_err());
+    }
+}
+
+#[cfg(test)]
+mod window_size_tests {
+    use super::{large_enough, parse_window_size};
+
+    #[test]
+    fn sizes_parse_with_either_x_and_spaces() {
+        assert_eq!(parse_window_size("800x600"), Ok((800, 600)));
+        assert_eq!(parse_window_size("800X600"), Ok((800, 600)));
+        assert_eq!(parse_window_size(" 800 x 600 "), Ok((800, 600)));
+        assert!(parse_window_size("-1x5").is_err());
+        assert!(parse_window_size("800").is_err());
+        assert!(parse_window_size("800x").is_err());
+    }
+
+    #[test]
+    fn only_windows_at_least_the_minimum_are_kept() {
+        // One process's main window, a toolbar, a tooltip and a window one
+        // pixel too narrow.
+        let windows = [Some((1920, 1080)), Some((800, 40)), Some((120, 24)), Some((799, 600))];
+        let kept: Vec<_> =
+            windows.into_iter().filter(|&size| large_enough(size, (800, 600))).collect();
+        assert_eq!(kept, [Some((1920, 1080))]);
+        assert!(large_enough(Some((800, 600)), (800, 600)));
+        assert!(!large_enough(None, (0, 0)));
+    }
+}
+
+#[cfg(test)]
+mod title_candidate_tests {
+    use super::test_support::{config, worker_args};
+    use super::*;
+
+    #[test]
+    fn title_alternatives_split_on_pipes() {
+        let candidates = title_candidates("New Title|Old Title| |new title", true);
+        assert_eq!(candidates, ["New Title", "Old Title"]);
+        assert!(title_candidates("|", true).is_empty());
+    }
+
+    #[test]
+    fn exe_workers_get_every_title_alternative_as_fallback() {
+        let options = config(&["--title-alternatives"]).options;
+        let mut cmd = Command::new("KeepActive.exe");
+        let titles = ["A|B".to_string(), "C".to_string()];
+        KeepAliveController::push_fallback_titles(&mut cmd, &titles, &options);
+        assert_eq!(worker_args(&cmd), ["--window", "A", "--window", "B", "--window", "C"]);
+    }
+}
+
+#[cfg(test)]
+mod shell_window_tests {
+    use super::is_shell_class;
+
+    #[test]
+    fn taskbar_and_desktop_classes_are_shell_windows() {
+        for class in ["Shell_TrayWnd", "Shell_SecondaryTrayWnd", "Progman", "WorkerW", "progman"] {
+            assert!(is_shell_class(class), "{}", class);
+        }
+    }
+
+    #[test]
+    fn app_classes_are_not() {
+        for class in ["Notepad", "Chrome_WidgetWin_1", "UnityWndClass", ""] {
+            assert!(!is_shell_class(class), "{}", class);
+        }
+    }
+}
+
+#[cfg(test)]
+mod console_window_tests {
+    use super::test_support::config;
+    use super::*;
+    use std::os::windows::process::CommandExt;
+    use windows::Win32::System::Threading::CREATE_NEW_CONSOLE;
+
+    fn process(pid: u32, parent_pid: u32, name: &str) -> ProcessEntry {
+        ProcessEntry { pid, parent_pid, name: name.to_string() }
+    }
+
+    #[test]
+    fn only_console_hosts_of_the_process_are_candidates() {
+        let processes = [
+            process(10, 4, "cmd.exe"),
+            process(11, 10, "conhost.exe"),
+            process(12, 10, "python.exe"),
+            process(13, 10, "OPENCONSOLE.EXE"),
+            process(14, 20, "conhost.exe"),
+        ];
+        assert_eq!(console_hosts(&processes, 10).collect::<Vec<_>>(), [11, 13]);
+        assert_eq!(console_hosts(&processes, 14).count(), 0);
+    }
+
+    /// Needs a desktop session whose default terminal is conhost, as on a
+    /// stock runner; Windows Terminal hosts the window in its own process.
+    #[test]
+    fn console_program_resolves_to_its_console_window() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .arg("/k")
+            .creation_flags(CREATE_NEW_CONSOLE.0)
+            .spawn()
+            .expect("cmd.exe should start");
+        let options = config(&["-e", "cmd.exe"]).options;
+        let found = (0..50).find_map(|_| {
+            std::thread::sleep(Duration::from_millis(100));
+            find_process_window(&TargetScan::default(), child.id(), &options)
+        });
+        let _ = child.kill();
+        let _ = child.wait();
+        let hwnd = found.expect("cmd.exe's console window should be found");
+        assert_eq!(window_class(hwnd), "ConsoleWindowClass");
+    }
+}
+
+#[cfg(test)]
+mod z_order_tests {
+    use super::*;
+
+    fn hwnd(handle: usize) -> HWND {
+        HWND(handle as *mut c_void)
+    }
+
+    /// Top to bottom: two processes with three and two windows each.
+    fn desktop() -> Vec<(HWND, u32)> {
+        vec![(hwnd(1), 100), (hwnd(2), 200), (hwnd(3), 100), (hwnd(4), 100), (hwnd(5), 200)]
+    }
+
+    #[test]
+    fn top_and_bottom_pick_either_end_of_the_process_windows() {
+        let windows = desktop();
+        let pick = |pids: &[u32], z_order| {
+            pick_in_z_order(&windows, pids, z_order, |_, _| true).map(|(window, _)| window)
+        };
+        assert_eq!(pick(&[100], ZOrder::Top), Some(hwnd(1)));
+        assert_eq!(pick(&[100], ZOrder::Bottom), Some(hwnd(4)));
+        assert_eq!(pick(&[200], ZOrder::Top), Some(hwnd(2)));
+        assert_eq!(pick(&[200], ZOrder::Bottom), Some(hwnd(5)));
+        assert_eq!(pick(&[100, 200], ZOrder::Bottom), Some(hwnd(5)));
+        assert_eq!(pick(&[300], ZOrder::Top), None);
+    }
+
+    #[test]
+    fn rejected_windows_are_skipped_from_either_end() {
+        let windows = desktop();
+        let usable = |window: HWND, _| window != hwnd(1) && window != hwnd(4);
+        let pick = |z_order| pick_in_z_order(&windows, &[100], z_order, usable);
+        assert_eq!(pick(ZOrder::Top), Some((hwnd(3), 100)));
+        assert_eq!(pick(ZOrder::Bottom), Some((hwnd(3), 100)));
+    }
+
+    #[test]
+    fn only_windows_up_to_the_match_are_checked() {
+        let windows = desktop();
+        let mut checked = Vec::new();
+        pick_in_z_order(&windows, &[100], ZOrder::Bottom, |window, _| {
+            checked.push(window);
+            window == hwnd(3)
+        });
+        assert_eq!(checked, [hwnd(4), hwnd(3)]);
+    }
+}
+
+#[cfg(test)]
+mod long_title_tests {
+    use super::test_support::config;
+    use super::*;
+    use windows::Win32::UI::WindowsAndMessaging::{CreateWindowExW, DestroyWindow, WS_VISIBLE};
+
+    /// A 1x1 tool window far off-screen: visible to EnumWindows and the
+    /// matchers, but never seen or activated.
+    struct OffscreenWindow(HWND);
+
+    impl OffscreenWindow {
+        fn titled(title: &str) -> Self {
+            let title = to_wide(title);
+            let hwnd = unsafe {
+                CreateWindowExW(
+                    WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+                    w!("STATIC"),
+                    PCWSTR(title.as_ptr()),
+                    WS_POPUP | WS_VISIBLE,
+                    -32000,
+                    -32000,
+                    1,
+                    1,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+            };
+            Self(hwnd.expect("CreateWindowExW failed"))
+        }
+    }
+
+    impl Drop for OffscreenWindow {
+        fn drop(&mut self) {
+            let _ = unsafe { DestroyWindow(self.0) };
+        }
+    }
+
+    #[test]
+    fn long_titles_are_read_and_matched_in_full() {
+        // Far past the short fixed buffers titles are often read into, with
+        // the only distinguishing part at the very end.
+        let tail = format!("keepactive-long-title-{}", std::process::id());
+        let title = format!("{}{}", "x".repeat(5000), tail);
+        let window = OffscreenWindow::titled(&title);
+        assert_eq!(window_title(window.0), title);
+
+        let exact = config(&[]).options;
+        assert_eq!(find_window_by_title(&TargetScan::default(), &title, &exact), Some(window.0));
+        let contains = config(&["--match-mode", "contains"]).options;
+        let found = find_window_by_title(&TargetScan::default(), &tail.to_uppercase(), &contains);
+        assert_eq!(found, Some(window.0));
+        let pattern = Regex::new(&format!("^x{{5000}}{}$", tail)).expect("pattern should compile");
+        assert_eq!(find_window_by_regex(&TargetScan::default(), &pattern, &exact), Some(window.0));
+    }
+}
+
+#[cfg(test)]
+mod worker_forwarding_tests {
+    use super::test_support::{config, forwarded, reparsed};
+
+    /// Command-line options and what reaches each worker after `--worker`:
+    /// only the options that differ from the defaults, in `push_args` order.
+    const FORWARDED: &[(&[&str], &[&str])] = &[
+        (&["-w", "CounterSide"], &[]),
+        (&["--double-tap", "50", "--coalesce"], &["--coalesce", "--double-tap", "50"]),
+        (&["--z-order", "top"], &[]),
+        (&["--z-order", "bottom"], &["--z-order", "bottom"]),
+        (
+            &["--jitter-ms", "20", "--interval-ms", "250"],
+            &["--interval-ms", "250", "--jitter-ms", "20"],
+        ),
+        (&["--title-alternatives"], &["--title-alternatives"]),
+        (&["--exclude", " Chrome Remote "], &["--exclude", "chrome remote"]),
+        (&["--no-steal", "--focus-messages"], &["--no-steal", "--focus-messages"]),
+        (&["--keepalive-key", "-e", "game.exe"], &["--keepalive-key=0x7E"]),
+        (&["--no-admin", "-e", "notepad.exe"], &["--no-admin"]),
+        (&["--rotate", "10s", "--all", "-e", "game.exe"], &["--rotate", "10s", "--all"]),
+    ];
+
+    #[test]
+    fn options_are_forwarded_to_workers() {
+        for (args, expected) in FORWARDED {
+            let mut worker = vec!["--worker"];
+            worker.extend(expected.iter());
+            assert_eq!(forwarded(&config(args).options), worker, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn worker_arguments_round_trip() {
+        let options = config(&["--coalesce", "--double-tap", "50"]).options;
+        let args = reparsed(&options, &["-e", "notepad.exe"]);
+        assert!(args.worker && args.coalesce);
+        assert_eq!(args.double_tap, Some(50));
+        assert_eq!(args.exe, ["notepad.exe"]);
+    }
+}
+
+#[cfg(test)]
+mod worker_log_tests {
+    use super::*;
+
+    #[test]
+    fn worker_logs_get_a_numbered_suffix() {
+        let log = worker_log_path(Path::new(r"C:\Logs\keepactive.log"), 3);
+        assert_eq!(log, Path::new(r"C:\Logs\keepactive-3.log"));
+        assert_eq!(worker_log_path(Path::new("keepactive"), 0), Path::new("keepactive-0"));
+    }
+}
+
+#[cfg(test)]
+mod schedule_tests {
+    use super::*;
+
+    #[test]
+    fn schedules_wrap_past_midnight() {
+        let at = |hours: u32, minutes: u32| (hours * 60 + minutes) * 60;
+        let overnight = Schedule {
+            from: parse_clock_time("22:00").expect("valid time"),
+            to: parse_clock_time("06:00").expect("valid time"),
+        };
+        assert_eq!(overnight.wait_at(at(23, 30)), None);
+        assert_eq!(overnight.wait_at(at(0, 0)), None);
+        assert_eq!(overnight.wait_at(at(5, 59)), None);
+        assert_eq!(overnight.wait_at(at(6, 0)), Some(Schedule::IDLE_POLL));
+        assert_eq!(overnight.wait_at(at(21, 59) + 50), Some(Duration::from_secs(10)));
+
+        let daytime = Schedule {
+            from: 9 * 60,
+            to: 17 * 60,
+        };
+        assert_eq!(daytime.wait_at(at(12, 0)), None);
+        assert_eq!(daytime.wait_at(at(17, 0)), Some(Schedule::IDLE_POLL));
+        assert!(parse_clock_time("24:00").is_err());
+        assert!(parse_clock_time("7:5").is_err());
+    }
+}
+
+#[cfg(test)]
+mod jitter_tests {
+    use super::*;
+
+    #[test]
+    fn jitter_stays_within_the_spread() {
+        let base = Duration::from_millis(100);
+        assert_eq!(Jitter::new(0).apply(base), base);
+        let mut jitter = Jitter::new(30);
+        let pauses: Vec<Duration> = (0..1000).map(|_| jitter.apply(base)).collect();
+        let range = Duration::from_millis(70)..=Duration::from_millis(130);
+        assert!(pauses.iter().all(|pause| range.contains(pause)));
+        assert!(pauses.iter().any(|&pause| pause != pauses[0]));
+    }
+}
+
+#[cfg(test)]
+mod config_file_tests {
+    use super::*;
+
+    const FILE: &str = r#"
+        window_titles = ["From File"]
+        process_names = ["file.exe"]
+        interval_ms = 500
+    "#;
+
+    fn merged(args: &[&str], text: &str) -> AppConfig {
+        let argv = std::iter::once("KeepActive").chain(args.iter().copied());
+        let args = Args::try_parse_from(argv).expect("arguments should parse");
+        let file: ConfigFile = toml::from_str(text).expect("config file should parse");
+        let mut config = AppConfig::from_args(&args);
+        config.merge_file(&args, file);
+        config
+    }
+
+    #[test]
+    fn file_fills_in_what_the_command_line_leaves_unset() {
+        let config = merged(&[], FILE);
+        assert_eq!(config.window_titles, ["From File"]);
+        assert_eq!(config.process_names, ["file.exe"]);
+        assert_eq!(config.options.interval_ms, 500);
+    }
+
+    #[test]
+    fn command_line_wins_over_the_file() {
+        let config = merged(&["-w", "CLI", "-e", "cli.exe", "--interval-ms", "200"], FILE);
+        assert_eq!(config.window_titles, ["CLI"]);
+        assert_eq!(config.process_names, ["cli.exe"]);
+        assert_eq!(config.options.interval_ms, 200);
+    }
+
+    #[test]
+    fn empty_file_keeps_the_defaults() {
+        let config = merged(&[], "");
+        assert_eq!(config.window_titles, [DEFAULT_WINDOW_TITLE]);
+        assert_eq!(config.options.interval_ms, REFRESH_INTERVAL_MS);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(toml::from_str::<ConfigFile>("window_title = [\"Typo\"]").is_err());
+    }
+
+    #[test]
+    fn only_a_missing_default_file_is_skipped() {
+        let missing = env::temp_dir().join("keepactive-missing-config.toml");
+        assert!(ConfigFile::read(&missing, false).expect("missing default is fine").is_none());
+        assert!(ConfigFile::read(&missing, true).is_err());
+    }
+}
+
+#[cfg(test)]
+mod gui_state_tests {
+    use super::GuiLists;
+
+    #[test]
+    fn saved_lists_round_trip() {
+        let lists = GuiLists {
+            window_titles: vec!["CounterSide".to_string()],
+            process_names: vec!["notepad.exe".to_string()],
+        };
+        let text = toml::to_string(&lists).expect("lists should serialize");
+        let restored = GuiLists::parse(&text).expect("saved lists should load");
+        assert_eq!(restored.window_titles, lists.window_titles);
+        assert_eq!(restored.process_names, lists.process_names);
+    }
+
+    #[test]
+    fn corrupt_or_partial_files_restore_nothing() {
+        assert!(GuiLists::parse("window_titles = [\"CounterSide\", \"Note").is_none());
+        assert!(GuiLists::parse("window_titles = [\"CounterSide\"]\n").is_none());
+        assert!(GuiLists::parse("\u{0}\u{1}garbage").is_none());
+    }
+}
+
+#[cfg(test)]
+mod launch_tests {
+    use super::test_support::{config, reparsed};
+    use super::*;
+
+    #[test]
+    fn launch_adds_its_exe_rule_and_forwards_hyphen_arguments() {
+        let config = config(&["--launch", r"C:\Games\game.exe", "--launch-args", "-windowed"]);
+        assert_eq!(config.process_names, ["game.exe"]);
+        let args = reparsed(&config.options, &[]);
+        assert_eq!(args.launch.as_deref(), Some(Path::new(r"C:\Games\game.exe")));
+        assert_eq!(args.launch_args, ["-windowed"]);
+    }
+}
+
+#[cfg(test)]
+mod exclude_tests {
+    use super::test_support::config;
+    use super::*;
+
+    #[test]
+    fn excluded_titles_match_substrings_ignoring_case() {
+        let options = config(&["--exclude", " Chrome Remote ", "--exclude", "chrome remote"])
+            .options;
+        assert_eq!(options.exclude_titles, ["chrome remote"]);
+        assert!(title_excluded("Chrome Remote Desktop", &options.exclude_titles));
+        assert!(!title_excluded("New Tab - Google Chrome", &options.exclude_titles));
+    }
+}
+
+#[cfg(test)]
+mod class_match_tests {
+    use super::*;
+
+    #[test]
+    fn class_rules_take_an_optional_title() {
+        let rule = ClassMatch::parse(" Chrome_WidgetWin_1 = YouTube ").expect("valid rule");
+        assert_eq!(rule.class, "Chrome_WidgetWin_1");
+        assert_eq!(rule.title.as_deref(), Some("YouTube"));
+        assert_eq!(rule.to_string(), "Chrome_WidgetWin_1=YouTube");
+        assert!(ClassMatch::parse("UnityWndClass").is_ok_and(|rule| rule.title.is_none()));
+        assert!(ClassMatch::parse("=YouTube").is_err());
+        assert!(ClassMatch::parse("UnityWndClass=").is_err());
+    }
+}
+
+#[cfg(test)]
+mod no_steal_tests {
+    use super::test_support::config;
+    use super::*;
+
+    #[test]
+    fn no_steal_rejects_methods_that_take_the_foreground() {
+        let options = config(&["--no-steal", "--focus-messages"]).options;
+        assert_eq!(activation_message(&options), "posted WM_NCACTIVATE+WM_ACTIVATE+WM_SETFOCUS");
+        assert!(Args::try_parse_from(["KeepActive", "--no-steal", "--escalate", "3"]).is_err());
+        let foreground = config(&["--no-steal", "--activate-method", "foreground"]).resolved();
+        assert!(foreground.validate().is_err());
+    }
+}
+
+#[cfg(test)]
+mod keepalive_key_tests {
+    use super::test_support::config;
+    use super::*;
+
+    #[test]
+    fn keys_parse_by_name_or_code() {
+        assert_eq!(parse_virtual_key("f15"), Ok(0x7E));
+        assert_eq!(parse_virtual_key("0x7e"), Ok(0x7E));
+        assert_eq!(parse_virtual_key("126"), Ok(0x7E));
+        assert!(parse_virtual_key("F25").is_err());
+        assert!(parse_virtual_key("0x100").is_err());
+        assert_eq!(key_name(0x7E), "F15");
+    }
+
+    #[test]
+    fn refused_send_input_falls_back_to_keybd_event() {
+        assert_eq!(after_send_input(2), Ok(KeyPath::SendInput));
+        assert_eq!(after_send_input(0), Ok(KeyPath::KeybdEvent));
+        assert!(after_send_input(1).is_err());
+    }
+
+    #[test]
+    fn a_bare_flag_means_f15() {
+        let options = config(&["--keepalive-key", "-e", "game.exe"]).options;
+        assert_eq!(options.keepalive_key, Some((0x7E, KeyMethod::Post)));
+    }
+}
+
+#[cfg(test)]
+mod target_interval_tests {
+    use super::test_support::config;
+    use super::*;
+
+    #[test]
+    fn rules_take_an_interval_suffix() {
+        let config = config(&["-w", "Foo@500", "-w", "me@example.com", "-e", "Game.exe@30000"])
+            .resolved();
+        assert_eq!(config.window_titles, ["Foo", "me@example.com"]);
+        assert_eq!(config.process_names, ["Game.exe"]);
+        assert_eq!(config.options.for_rule(&config.target_intervals, "Foo").interval_ms, 500);
+        assert_eq!(
+            config.options.for_rule(&config.target_intervals, "Game.exe").interval_ms,
+            30_000
+        );
+        let plain = config.options.for_rule(&config.target_intervals, "me@example.com");
+        assert_eq!(plain.interval_ms, REFRESH_INTERVAL_MS);
+    }
+
+    #[test]
+    fn only_digits_right_after_the_at_count() {
+        let config = config(&["-w", "Room @ 10", "-w", "Lobby@ 10", "-w", "Hall@10"]).resolved();
+        assert_eq!(config.window_titles, ["Room @ 10", "Lobby@ 10", "Hall"]);
+        assert_eq!(config.target_intervals.len(), 1);
+    }
+
+    #[test]
+    fn suffixes_are_rejected_where_one_worker_runs_every_rule() {
+        for flags in [&["--all"][..], &["--rotate", "10s"][..]] {
+            let mut args = vec!["-w", "Foo@500", "-e", "game.exe"];
+            args.extend_from_slice(flags);
+            assert!(config(&args).resolved().validate().is_err(), "{:?}", flags);
+        }
+        assert!(config(&["-w", "Foo@500", "-e", "game.exe"]).resolved().validate().is_ok());
+    }
+}
+
+#[cfg(test)]
+mod rotation_tests {
+    use super::test_support::config;
+    use super::{next_turn, stable_turn_order};
+
+    #[test]
+    fn turns_skip_missing_windows_and_wrap() {
+        let windows = [Some(10), None, Some(30)];
+        assert_eq!(next_turn(&windows, None), Some(0));
+        assert_eq!(next_turn(&windows, Some(0)), Some(2));
+        assert_eq!(next_turn(&windows, Some(2)), Some(0));
+        assert_eq!(next_turn::<usize>(&[None, None], Some(0)), None);
+    }
+
+    #[test]
+    fn turns_follow_handles_not_z_order() {
+        // Each window is given a turn once per round however the scan
+        // happens to order them, as it does after each foreground change.
+        let scans = [
+            [30, 10, 20],
+            [10, 20, 30],
+            [20, 30, 10],
+            [30, 20, 10],
+            [20, 10, 30],
+            [10, 30, 20],
+        ];
+        let mut last = None;
+        let mut taken = Vec::new();
+        for scan in scans {
+            let (handles, cursor) = stable_turn_order(scan.to_vec(), last);
+            let windows: Vec<Option<usize>> = handles.into_iter().map(Some).collect();
+            let next = windows[next_turn(&windows, cursor).expect("every window resolves")];
+            taken.extend(next);
+            last = next;
+        }
+        assert_eq!(taken, [10, 20, 30, 10, 20, 30]);
+    }
+
+    #[test]
+    fn a_closed_window_hands_its_turn_on() {
+        // 20 was the last turn and has closed since: 30 still comes next.
+        assert_eq!(stable_turn_order(vec![30, 10], Some(20)), (vec![10, 30], Some(0)));
+        assert_eq!(stable_turn_order(vec![30], Some(20)), (vec![30], None));
+    }
+
+    #[test]
+    fn rotation_can_take_every_matching_window() {
+        let config = config(&["--rotate", "10s", "--all", "-e", "game.exe"]).resolved();
+        assert!(config.validate().is_ok());
+    }
+}
+
+#[cfg(test)]
+mod quote_argument_tests {
+    use super::*;
+    use windows::Win32::{Foundation::{LocalFree, HLOCAL}, UI::Shell::CommandLineToArgvW};
+
+    /// What the relaunched process sees: the arguments as CommandLineToArgvW
+    /// splits them back out of the joined command line.
+    fn round_trip(args: &[&str]) -> Vec<String> {
+        let quoted: Vec<String> = args.iter().map(|arg| quote_argument(arg)).collect();
+        let line = to_wide(&format!("KeepActive.exe {}", quoted.join(" ")));
+        let mut count = 0;
+        unsafe {
+            let argv = CommandLineToArgvW(PCWSTR(line.as_ptr()), &mut count);
+            assert!(!argv.is_null(), "CommandLineToArgvW failed");
+            let parsed = (1..count as usize)
+                .map(|index| (*argv.add(index)).to_string().expect("argument should be UTF-16"))
+                .collect();
+            let _ = LocalFree(HLOCAL(argv.cast()));
+            parsed
+        }
+    }
+
+    #[test]
+    fn plain_argument_is_left_alone() {
+        assert_eq!(quote_argument("--exe"), "--exe");
+        assert_eq!(quote_argument("game.exe"), "game.exe");
+    }
+
+    #[test]
+    fn whitespace_is_quoted() {
+        assert_eq!(quote_argument("Counter Side"), r#""Counter Side""#);
+        assert_eq!(quote_argument("a\tb"), "\"a\tb\"");
+    }
+
+    #[test]
+    fn trailing_backslashes_survive_the_closing_quote() {
+        // Unquoted, backslashes are literal; quoted, they are doubled so the
+        // closing quote stays a quote.
+        assert_eq!(quote_argument(r"C:\Games\"), r"C:\Games\");
+        assert_eq!(quote_argument(r"C:\My Games\"), r#""C:\My Games\\""#);
+        assert_eq!(quote_argument(r"C:\My Games\\"), r#""C:\My Games\\\\""#);
+    }
+
+    #[test]
+    fn embedded_quotes_are_escaped() {
+        assert_eq!(quote_argument(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(quote_argument(r#"a\"b"#), r#""a\\\"b""#);
+        assert_eq!(quote_argument(r"a\b"), r"a\b");
+    }
+
+    #[test]
+    fn empty_argument_is_kept() {
+        assert_eq!(quote_argument(""), r#""""#);
+    }
+
+    #[test]
+    fn arguments_round_trip_through_command_line_to_argv() {
+        let args = [
+            "--window",
+            "Counter Side",
+            r"C:\Games\",
+            r"C:\My Games\",
+            r"C:\My Games\\",
+            r#"say "hi""#,
+            r#"a\"b"#,
+            r#"a\\"b"#,
+            r"\\server\share\",
+            "",
+            "a\tb",
+            r#"""#,
+        ];
+        assert_eq!(round_trip(&args), args);
+    }
+}